@@ -1,201 +1,350 @@
 #![allow(clippy::manual_map)]
 
-use std::rc::Rc;
-
 use crate::{
-    lexer::{Token, TokenKind},
+    edit::replace_node,
+    lexer::TokenKind,
     source::SourceRange,
+    syntax::{SyntaxKind, SyntaxNode, SyntaxNodeChild, SyntaxToken},
 };
 
-pub(crate) trait AstNode: Sized {
+pub trait AstNode: Sized {
     fn cast(syntax: &SyntaxNode) -> Option<Self>;
     fn range(&self) -> SourceRange;
+    fn syntax(&self) -> &SyntaxNode;
+
+    /// The document containing the node, `None` for nodes built on their own
+    fn document(&self) -> Option<Document> {
+        Document::cast(&self.syntax().root())
+    }
+
+    /// Replace the node with `node` in a copy of its tree, and return the new node.
+    /// The new node takes over the trivia around the replaced one.
+    fn replace_with(&self, node: Self) -> Self {
+        Self::cast(&replace_node(self.syntax(), node.syntax())).unwrap()
+    }
 }
 
-type SyntaxResult<T> = Result<T, ()>;
+/// A node or token required by the grammar is missing from the tree.
+/// The parser has already reported a diagnostic for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissingSyntax;
+
+pub type SyntaxResult<T> = Result<T, MissingSyntax>;
 
-#[derive(Debug)]
-pub(crate) struct Document {
+#[derive(Debug, Clone)]
+pub struct Document {
     syntax: SyntaxNode,
 }
 
 impl Document {
-    #[cfg(test)]
-    pub(crate) fn syntax(&self) -> SyntaxNode {
-        self.syntax.clone()
+    pub fn statements(&self) -> impl IntoIterator<Item = Statement> + '_ {
+        get_child_nodes(&self.syntax)
     }
 
-    pub(crate) fn statements(&self) -> impl IntoIterator<Item = Statement> + '_ {
-        get_child_nodes(&self.syntax)
+    /// End of the file, whose leading trivia are the comments after the last statement
+    pub fn eof(&self) -> SyntaxResult<SyntaxToken> {
+        get_token(&self.syntax, TokenKind::EOF)
     }
 }
 
-#[derive(Debug)]
-pub(crate) enum Statement {
+#[derive(Debug, Clone)]
+pub enum Statement {
     Node(NodeDefinition),
 }
 
-#[derive(Debug)]
-pub(crate) struct NodeDefinition {
+#[derive(Debug, Clone)]
+pub struct NodeDefinition {
     syntax: SyntaxNode,
 }
 
 impl NodeDefinition {
     // Zephyr mentioned that one node can have multiple labels, but I found no document for that
     // ref: https://docs.zephyrproject.org/latest/build/dts/intro-syntax-structure.html#nodes
-    pub(crate) fn label(&self) -> Option<Label> {
+    pub fn label(&self) -> Option<Label> {
         get_child_node(&self.syntax).ok()
     }
 
     // name@address, or "/" for root node
-    pub(crate) fn identifier(&self) -> SyntaxResult<NodeIdentifier> {
+    pub fn identifier(&self) -> SyntaxResult<NodeIdentifier> {
         get_child_node(&self.syntax)
     }
 
-    pub(crate) fn body(&self) -> SyntaxResult<NodeBody> {
+    pub fn body(&self) -> SyntaxResult<NodeBody> {
         get_child_node(&self.syntax)
     }
+
+    pub fn child_nodes(&self) -> impl Iterator<Item = NodeDefinition> {
+        self.body_entries().filter_map(|entry| match entry {
+            NodeBodyEntry::Node(node) => Some(node),
+            NodeBodyEntry::Property(_) => None,
+        })
+    }
+
+    pub fn properties(&self) -> impl Iterator<Item = PropertyDefinition> {
+        self.body_entries().filter_map(|entry| match entry {
+            NodeBodyEntry::Property(property) => Some(property),
+            NodeBodyEntry::Node(_) => None,
+        })
+    }
+
+    /// Whether `compatible` is one of the strings of the node's `compatible` property
+    pub fn is_compatible(&self, compatible: &str) -> bool {
+        self.properties()
+            .filter_map(|property| match property {
+                PropertyDefinition::NonBool(property) => Some(property),
+                PropertyDefinition::Bool(_) => None,
+            })
+            .filter(|property| {
+                property
+                    .name()
+                    .is_ok_and(|name| name.syntax().text() == "compatible")
+            })
+            .filter_map(|property| property.values().ok())
+            .flat_map(|values| values.into_iter())
+            .any(|value| match value {
+                PropertyValue::String(string) => {
+                    let text = string.syntax().text();
+                    text.get(1..text.len() - 1) == Some(compatible)
+                }
+                PropertyValue::Array(_) => false,
+            })
+    }
+
+    fn body_entries(&self) -> impl Iterator<Item = NodeBodyEntry> {
+        self.body()
+            .and_then(|body| body.entries())
+            .map(|entries| entries.into_iter().collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+    }
 }
 
-#[derive(Debug)]
-pub(crate) struct Label {
+#[derive(Debug, Clone)]
+pub struct Label {
     syntax: SyntaxNode,
 }
 
-#[derive(Debug)]
-pub(crate) enum NodeIdentifier {
+#[derive(Debug, Clone)]
+pub enum NodeIdentifier {
     Root(RootNodeIdentifier),
     NonRoot(NonRootNodeIdentifier),
 }
 
-#[derive(Debug)]
-pub(crate) struct NonRootNodeIdentifier {
+#[derive(Debug, Clone)]
+pub struct NonRootNodeIdentifier {
     syntax: SyntaxNode,
 }
 
 impl NonRootNodeIdentifier {
-    pub(crate) fn name(&self) -> SyntaxResult<NodeName> {
+    pub fn name(&self) -> SyntaxResult<NodeName> {
         get_child_node(&self.syntax)
     }
 
-    pub(crate) fn address(&self) -> Option<NodeAddress> {
+    pub fn address(&self) -> Option<NodeAddress> {
         get_child_node(&self.syntax).ok()
     }
 }
 
-#[derive(Debug)]
-pub(crate) struct NodeName {
+#[derive(Debug, Clone)]
+pub struct NodeName {
     syntax: SyntaxNode,
 }
 
-#[derive(Debug)]
-pub(crate) struct NodeAddress {
+#[derive(Debug, Clone)]
+pub struct NodeAddress {
     syntax: SyntaxNode,
 }
 
-#[derive(Debug)]
-pub(crate) struct RootNodeIdentifier {
+#[derive(Debug, Clone)]
+pub struct RootNodeIdentifier {
     syntax: SyntaxNode,
 }
 
-#[derive(Debug)]
-pub(crate) struct NodeBody {
+#[derive(Debug, Clone)]
+pub struct NodeBody {
     syntax: SyntaxNode,
 }
 
 impl NodeBody {
-    pub(crate) fn entries(&self) -> SyntaxResult<NodeBodyEntries> {
+    pub fn entries(&self) -> SyntaxResult<NodeBodyEntries> {
         get_child_node(&self.syntax)
     }
 
-    pub(crate) fn r_curly(&self) -> SyntaxResult<Token> {
+    pub fn l_curly(&self) -> SyntaxResult<SyntaxToken> {
+        get_token(&self.syntax, TokenKind::L_CURLY)
+    }
+
+    pub fn r_curly(&self) -> SyntaxResult<SyntaxToken> {
         get_token(&self.syntax, TokenKind::R_CURLY)
     }
+
+    pub fn semicolon(&self) -> SyntaxResult<SyntaxToken> {
+        get_token(&self.syntax, TokenKind::SEMICOLON)
+    }
 }
 
-pub(crate) struct NodeBodyEntries {
+#[derive(Debug, Clone)]
+pub struct NodeBodyEntries {
     syntax: SyntaxNode,
 }
 
-#[derive(Debug)]
-pub(crate) enum NodeBodyEntry {
+impl NodeBodyEntries {
+    pub fn entries(&self) -> impl IntoIterator<Item = NodeBodyEntry> + '_ {
+        get_child_nodes(&self.syntax)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum NodeBodyEntry {
     Node(NodeDefinition),
     Property(PropertyDefinition),
 }
 
-#[derive(Debug)]
-pub(crate) enum PropertyDefinition {
+#[derive(Debug, Clone)]
+pub enum PropertyDefinition {
     Bool(BoolPropertyDefinition),
     NonBool(NonBoolPropertyDefinition),
 }
 
-#[derive(Debug)]
-pub(crate) struct BoolPropertyDefinition {
+impl PropertyDefinition {
+    pub fn name(&self) -> SyntaxResult<PropertyName> {
+        match self {
+            PropertyDefinition::Bool(property) => property.name(),
+            PropertyDefinition::NonBool(property) => property.name(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BoolPropertyDefinition {
     syntax: SyntaxNode,
 }
 
 impl BoolPropertyDefinition {
-    pub(crate) fn name(&self) -> SyntaxResult<PropertyName> {
+    pub fn name(&self) -> SyntaxResult<PropertyName> {
         get_child_node(&self.syntax)
     }
+
+    pub fn semicolon(&self) -> SyntaxResult<SyntaxToken> {
+        get_token(&self.syntax, TokenKind::SEMICOLON)
+    }
 }
 
-#[derive(Debug)]
-pub(crate) struct NonBoolPropertyDefinition {
+#[derive(Debug, Clone)]
+pub struct NonBoolPropertyDefinition {
     syntax: SyntaxNode,
 }
 
 impl NonBoolPropertyDefinition {
-    pub(crate) fn name(&self) -> SyntaxResult<PropertyName> {
+    pub fn name(&self) -> SyntaxResult<PropertyName> {
         get_child_node(&self.syntax)
     }
 
-    pub(crate) fn values(&self) -> SyntaxResult<PropertyValues> {
+    pub fn equal(&self) -> SyntaxResult<SyntaxToken> {
+        get_token(&self.syntax, TokenKind::EQUAL)
+    }
+
+    pub fn values(&self) -> SyntaxResult<PropertyValues> {
         get_child_node(&self.syntax)
     }
 }
 
-#[derive(Debug)]
-pub(crate) struct PropertyName {
+#[derive(Debug, Clone)]
+pub struct PropertyName {
     syntax: SyntaxNode,
 }
 
 /// Property values may be defined as an array of 32-bit integer cells, as null-terminated strings, as bytestrings or a combination of these.
 /// https://devicetree-specification.readthedocs.io/en/latest/chapter6-source-language.html
-#[derive(Debug)]
-pub(crate) struct PropertyValues {
+#[derive(Debug, Clone)]
+pub struct PropertyValues {
     syntax: SyntaxNode,
 }
 
-#[derive(Debug)]
-pub(crate) enum PropertyValue {
+impl PropertyValues {
+    pub fn values(&self) -> impl IntoIterator<Item = PropertyValue> + '_ {
+        get_child_nodes(&self.syntax)
+    }
+
+    /// Commas between the values
+    pub fn commas(&self) -> Vec<SyntaxToken> {
+        get_tokens(&self.syntax, TokenKind::COMMA)
+    }
+
+    pub fn semicolon(&self) -> SyntaxResult<SyntaxToken> {
+        get_token(&self.syntax, TokenKind::SEMICOLON)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PropertyValue {
     Array(ArrayValue),
     String(StringValue),
 }
 
-#[derive(Debug)]
-pub(crate) struct ArrayValue {
+#[derive(Debug, Clone)]
+pub struct ArrayValue {
     syntax: SyntaxNode,
 }
 
-#[derive(Debug)]
-pub(crate) enum ArrayCell {
+impl ArrayValue {
+    pub fn cells(&self) -> impl IntoIterator<Item = ArrayCell> + '_ {
+        get_child_nodes(&self.syntax)
+    }
+
+    pub fn l_angle(&self) -> SyntaxResult<SyntaxToken> {
+        get_token(&self.syntax, TokenKind::L_ANGLE)
+    }
+
+    pub fn r_angle(&self) -> SyntaxResult<SyntaxToken> {
+        get_token(&self.syntax, TokenKind::R_ANGLE)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ArrayCell {
     Int(IntCell),
+    Phandle(PhandleCell),
+    Macro(MacroCell),
+}
+
+#[derive(Debug, Clone)]
+pub struct IntCell {
+    syntax: SyntaxNode,
+}
+
+/// Reference to a labeled node, like `&kp`
+#[derive(Debug, Clone)]
+pub struct PhandleCell {
+    syntax: SyntaxNode,
 }
 
-#[derive(Debug)]
-pub(crate) struct IntCell {
+impl PhandleCell {
+    pub fn label(&self) -> SyntaxResult<SyntaxToken> {
+        get_token(&self.syntax, TokenKind::NAME)
+    }
+}
+
+/// Unexpanded preprocessor macro, like `Q` or `RC(0,1)`
+#[derive(Debug, Clone)]
+pub struct MacroCell {
     syntax: SyntaxNode,
 }
 
-#[derive(Debug)]
-pub(crate) struct StringValue {
+impl MacroCell {
+    pub fn name(&self) -> SyntaxResult<SyntaxToken> {
+        get_token(&self.syntax, TokenKind::NAME)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StringValue {
     syntax: SyntaxNode,
 }
 
 impl AstNode for Document {
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::Document) {
+        if syntax.kind() == SyntaxKind::Document {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -205,7 +354,11 @@ impl AstNode for Document {
     }
 
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 }
 
@@ -222,15 +375,25 @@ impl AstNode for Statement {
             Self::Node(node) => node.range(),
         }
     }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::Node(node) => node.syntax(),
+        }
+    }
 }
 
 impl AstNode for NodeDefinition {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::NodeDefinition) {
+        if syntax.kind() == SyntaxKind::NodeDefinition {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -242,11 +405,15 @@ impl AstNode for NodeDefinition {
 
 impl AstNode for Label {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::Label) {
+        if syntax.kind() == SyntaxKind::Label {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -273,15 +440,26 @@ impl AstNode for NodeIdentifier {
             None
         }
     }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::Root(root) => root.syntax(),
+            Self::NonRoot(iden) => iden.syntax(),
+        }
+    }
 }
 
 impl AstNode for NonRootNodeIdentifier {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::NonRootNodeIdentifier) {
+        if syntax.kind() == SyntaxKind::NonRootNodeIdentifier {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -293,11 +471,15 @@ impl AstNode for NonRootNodeIdentifier {
 
 impl AstNode for NodeName {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::NodeName) {
+        if syntax.kind() == SyntaxKind::NodeName {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -309,11 +491,15 @@ impl AstNode for NodeName {
 
 impl AstNode for NodeAddress {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::NodeAddress) {
+        if syntax.kind() == SyntaxKind::NodeAddress {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -325,11 +511,15 @@ impl AstNode for NodeAddress {
 
 impl AstNode for RootNodeIdentifier {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::RootNodeIdentifier) {
+        if syntax.kind() == SyntaxKind::RootNodeIdentifier {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -341,11 +531,15 @@ impl AstNode for RootNodeIdentifier {
 
 impl AstNode for NodeBody {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::NodeBody) {
+        if syntax.kind() == SyntaxKind::NodeBody {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -357,11 +551,15 @@ impl AstNode for NodeBody {
 
 impl AstNode for NodeBodyEntries {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::NodeBodyEntries) {
+        if syntax.kind() == SyntaxKind::NodeBodyEntries {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -388,6 +586,13 @@ impl AstNode for NodeBodyEntry {
             None
         }
     }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::Node(node) => node.syntax(),
+            Self::Property(prop) => prop.syntax(),
+        }
+    }
 }
 
 impl AstNode for PropertyDefinition {
@@ -407,15 +612,26 @@ impl AstNode for PropertyDefinition {
             None
         }
     }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::Bool(prop) => prop.syntax(),
+            Self::NonBool(prop) => prop.syntax(),
+        }
+    }
 }
 
 impl AstNode for BoolPropertyDefinition {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::BoolPropertyDefinition) {
+        if syntax.kind() == SyntaxKind::BoolPropertyDefinition {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -427,11 +643,15 @@ impl AstNode for BoolPropertyDefinition {
 
 impl AstNode for NonBoolPropertyDefinition {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::NonBoolPropertyDefinition) {
+        if syntax.kind() == SyntaxKind::NonBoolPropertyDefinition {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -443,11 +663,15 @@ impl AstNode for NonBoolPropertyDefinition {
 
 impl AstNode for PropertyName {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::PropertyName) {
+        if syntax.kind() == SyntaxKind::PropertyName {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -459,11 +683,15 @@ impl AstNode for PropertyName {
 
 impl AstNode for PropertyValues {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::PropertyValues) {
+        if syntax.kind() == SyntaxKind::PropertyValues {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -490,15 +718,26 @@ impl AstNode for PropertyValue {
             None
         }
     }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::Array(a) => a.syntax(),
+            Self::String(s) => s.syntax(),
+        }
+    }
 }
 
 impl AstNode for ArrayValue {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::ArrayValue) {
+        if syntax.kind() == SyntaxKind::ArrayValue {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -512,25 +751,83 @@ impl AstNode for ArrayCell {
     fn range(&self) -> SourceRange {
         match self {
             Self::Int(i) => i.range(),
+            Self::Phandle(p) => p.range(),
+            Self::Macro(m) => m.range(),
         }
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
         if let Some(identifier) = IntCell::cast(syntax) {
             Some(Self::Int(identifier))
+        } else if let Some(identifier) = PhandleCell::cast(syntax) {
+            Some(Self::Phandle(identifier))
+        } else if let Some(identifier) = MacroCell::cast(syntax) {
+            Some(Self::Macro(identifier))
         } else {
             None
         }
     }
+
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::Int(i) => i.syntax(),
+            Self::Phandle(p) => p.syntax(),
+            Self::Macro(m) => m.syntax(),
+        }
+    }
 }
 
 impl AstNode for IntCell {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+
+    fn cast(syntax: &SyntaxNode) -> Option<Self> {
+        if syntax.kind() == SyntaxKind::IntCell {
+            Some(Self {
+                syntax: syntax.clone(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl AstNode for PhandleCell {
+    fn range(&self) -> SourceRange {
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
+    }
+
+    fn cast(syntax: &SyntaxNode) -> Option<Self> {
+        if syntax.kind() == SyntaxKind::PhandleCell {
+            Some(Self {
+                syntax: syntax.clone(),
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl AstNode for MacroCell {
+    fn range(&self) -> SourceRange {
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::IntCell) {
+        if syntax.kind() == SyntaxKind::MacroCell {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -542,11 +839,15 @@ impl AstNode for IntCell {
 
 impl AstNode for StringValue {
     fn range(&self) -> SourceRange {
-        self.syntax.range
+        self.syntax.range()
+    }
+
+    fn syntax(&self) -> &SyntaxNode {
+        &self.syntax
     }
 
     fn cast(syntax: &SyntaxNode) -> Option<Self> {
-        if matches!(syntax.kind, SyntaxKind::StringValue) {
+        if syntax.kind() == SyntaxKind::StringValue {
             Some(Self {
                 syntax: syntax.clone(),
             })
@@ -586,100 +887,9 @@ impl IntoIterator for ArrayValue {
     }
 }
 
-pub(crate) type SyntaxNode = Rc<SyntaxNodeData>;
-
-#[derive(Debug)]
-pub(crate) struct SyntaxNodeData {
-    pub(crate) kind: SyntaxKind,
-    pub(crate) children: Vec<SyntaxNodeChild>,
-    pub(crate) range: SourceRange,
-}
-
-pub(crate) struct SyntaxNodeBuilder {
-    kind: Option<SyntaxKind>,
-    children: Vec<SyntaxNodeChild>,
-    range: Option<SourceRange>,
-}
-
-impl SyntaxNodeBuilder {
-    pub(crate) fn new() -> Self {
-        Self {
-            kind: None,
-            children: Vec::new(),
-            range: None,
-        }
-    }
-
-    pub(crate) fn push_node(&mut self, node: SyntaxNode) {
-        self.children.push(SyntaxNodeChild::Tree(node));
-    }
-
-    pub(crate) fn push_token(&mut self, token: Token) {
-        self.children.push(SyntaxNodeChild::Token(token));
-    }
-
-    pub(crate) fn kind(&mut self, kind: SyntaxKind) {
-        self.kind = Some(kind)
-    }
-
-    pub(crate) fn range(&mut self, range: SourceRange) {
-        self.range = Some(range)
-    }
-
-    pub(crate) fn build(self) -> SyntaxNode {
-        Rc::new(SyntaxNodeData {
-            kind: self.kind.unwrap(),
-            children: self.children,
-            range: self.range.unwrap(),
-        })
-    }
-}
-
-#[derive(Debug, Clone)]
-pub(crate) enum SyntaxNodeChild {
-    Token(Token),
-    Tree(SyntaxNode),
-}
-
-impl SyntaxNodeChild {
-    fn as_token(&self) -> Option<&Token> {
-        match self {
-            SyntaxNodeChild::Token(token) => Some(token),
-            _ => None,
-        }
-    }
-
-    fn as_node(&self) -> Option<&SyntaxNode> {
-        match self {
-            SyntaxNodeChild::Tree(node) => Some(node),
-            _ => None,
-        }
-    }
-}
-
-#[derive(Debug)]
-pub(crate) enum SyntaxKind {
-    Document,
-    NodeDefinition,
-    Label,
-    RootNodeIdentifier,
-    NonRootNodeIdentifier,
-    NodeName,
-    NodeAddress,
-    NodeBody,
-    NodeBodyEntries,
-    BoolPropertyDefinition,
-    NonBoolPropertyDefinition,
-    PropertyName,
-    PropertyValues,
-    ArrayValue,
-    IntCell,
-    StringValue,
-}
-
 fn get_child_nodes<'a, T: AstNode + 'a>(syntax: &'a SyntaxNode) -> Vec<T> {
     syntax
-        .children
+        .children()
         .iter()
         .filter_map(SyntaxNodeChild::as_node)
         .filter_map(T::cast)
@@ -688,19 +898,29 @@ fn get_child_nodes<'a, T: AstNode + 'a>(syntax: &'a SyntaxNode) -> Vec<T> {
 
 fn get_child_node<T: AstNode>(syntax: &SyntaxNode) -> SyntaxResult<T> {
     syntax
-        .children
+        .children()
         .iter()
         .filter_map(SyntaxNodeChild::as_node)
         .find_map(T::cast)
-        .ok_or(())
+        .ok_or(MissingSyntax)
+}
+
+fn get_tokens(syntax: &SyntaxNode, kind: TokenKind) -> Vec<SyntaxToken> {
+    syntax
+        .children()
+        .iter()
+        .filter_map(SyntaxNodeChild::as_token)
+        .filter(|token| token.kind() == kind)
+        .cloned()
+        .collect()
 }
 
-fn get_token(syntax: &SyntaxNode, kind: TokenKind) -> SyntaxResult<Token> {
+fn get_token(syntax: &SyntaxNode, kind: TokenKind) -> SyntaxResult<SyntaxToken> {
     syntax
-        .children
+        .children()
         .iter()
         .filter_map(SyntaxNodeChild::as_token)
-        .find(|token| token.kind == kind)
+        .find(|token| token.kind() == kind)
         .cloned()
-        .ok_or(())
+        .ok_or(MissingSyntax)
 }
@@ -0,0 +1,283 @@
+//! `zmk-styler.toml` config files.
+//!
+//! The config of a formatted file is found by walking up its directories:
+//! every `zmk-styler.toml` on the way sets the options it lists, the ones closer
+//! to the file overriding the ones further up, until a config file sets `root = true`.
+//! ```toml
+//! root = true
+//! max_width = 120
+//! layout = "glove80"
+//! ```
+//!
+//! Options:
+//! - `root`: don't look for config files in parent directories.
+//! - `indent_width`, `max_width`, `bindings_grid` and `pad_matrix_transform`, see [`Config`].
+//! - `indent_style`: `"space"` or `"tab"`.
+//! - `end_of_line`: `"auto"`, `"lf"` or `"crlf"`, and `insert_final_newline`, see [`Config`].
+//! - `layout`: a bundled layout, like `"corne"`, or the path of a QMK `info.json`
+//!   relative to the config file, with `layout_name` picking one of its layouts.
+//! - `layout_rows`: the number of keys of every row, or of its two halves,
+//!   like `[[6, 6], [6, 6], [3, 3]]`, with `split_gap` columns between the halves (1 by default).
+//!
+//! Without any `zmk-styler.toml`, the options from `.editorconfig` files are used instead:
+//! `indent_style`, `indent_size` (or `tab_width`), `end_of_line`, `max_line_length`
+//! and `insert_final_newline`.
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::Display,
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    editorconfig::editorconfig_properties,
+    formatter::{Config, EndOfLine, IndentStyle},
+    layout::Layout,
+};
+
+pub const CONFIG_FILE_NAME: &str = "zmk-styler.toml";
+
+/// Options listed in a config file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigOptions {
+    #[serde(default)]
+    root: bool,
+    indent_width: Option<u32>,
+    indent_style: Option<IndentStyle>,
+    max_width: Option<u32>,
+    bindings_grid: Option<bool>,
+    pad_matrix_transform: Option<bool>,
+    end_of_line: Option<EndOfLine>,
+    insert_final_newline: Option<bool>,
+    layout: Option<String>,
+    layout_name: Option<String>,
+    layout_rows: Option<Vec<Vec<usize>>>,
+    split_gap: Option<usize>,
+}
+
+/// Options of a config file, with its layout resolved
+struct ConfigOverrides {
+    root: bool,
+    indent_width: Option<u32>,
+    indent_style: Option<IndentStyle>,
+    max_width: Option<u32>,
+    bindings_grid: Option<bool>,
+    pad_matrix_transform: Option<bool>,
+    end_of_line: Option<EndOfLine>,
+    insert_final_newline: Option<bool>,
+    layout: Option<Layout>,
+}
+
+impl ConfigOverrides {
+    /// Options of a config file with content `text`, resolving paths from `dir`
+    fn parse(text: &str, dir: &Path) -> Result<Self, String> {
+        let options: ConfigOptions = toml::from_str(text).map_err(|error| error.to_string())?;
+        if options.max_width == Some(0) {
+            return Err("`max_width` should be at least 1".to_owned());
+        }
+        if options.layout_name.is_some() && options.layout.is_none() {
+            return Err("`layout_name` needs `layout` to be a QMK info.json".to_owned());
+        }
+        if options.split_gap.is_some() && options.layout_rows.is_none() {
+            return Err("`split_gap` needs `layout_rows`".to_owned());
+        }
+        let layout = match (options.layout, options.layout_rows) {
+            (Some(_), Some(_)) => {
+                return Err("only one of `layout` and `layout_rows` can be set".to_owned())
+            }
+            (Some(layout), None) => {
+                Some(read_layout(&layout, options.layout_name.as_deref(), dir)?)
+            }
+            (None, Some(rows)) => Some(
+                Layout::from_rows(&rows, options.split_gap.unwrap_or(1))
+                    .map_err(|error| format!("invalid `layout_rows`: {error}"))?,
+            ),
+            (None, None) => None,
+        };
+        Ok(Self {
+            root: options.root,
+            indent_width: options.indent_width,
+            indent_style: options.indent_style,
+            max_width: options.max_width,
+            bindings_grid: options.bindings_grid,
+            pad_matrix_transform: options.pad_matrix_transform,
+            end_of_line: options.end_of_line,
+            insert_final_newline: options.insert_final_newline,
+            layout,
+        })
+    }
+
+    fn read(path: &Path) -> Result<Self, ConfigError> {
+        let text = fs::read_to_string(path).map_err(|error| ConfigError::new(path, error))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        Self::parse(&text, dir).map_err(|error| ConfigError::new(path, error))
+    }
+
+    fn apply(self, config: &mut Config) {
+        if let Some(indent_width) = self.indent_width {
+            config.indent_width = indent_width;
+        }
+        if let Some(indent_style) = self.indent_style {
+            config.indent_style = indent_style;
+        }
+        if let Some(max_width) = self.max_width {
+            config.max_width = max_width;
+        }
+        if let Some(bindings_grid) = self.bindings_grid {
+            config.bindings_grid = bindings_grid;
+        }
+        if let Some(pad_matrix_transform) = self.pad_matrix_transform {
+            config.pad_matrix_transform = pad_matrix_transform;
+        }
+        if let Some(end_of_line) = self.end_of_line {
+            config.end_of_line = end_of_line;
+        }
+        if let Some(insert_final_newline) = self.insert_final_newline {
+            config.insert_final_newline = insert_final_newline;
+        }
+        if let Some(layout) = self.layout {
+            config.layout = Some(layout);
+        }
+    }
+}
+
+/// A bundled layout, or else a QMK `info.json` at `layout` relative to `dir`,
+/// with `name` picking one of its layouts
+pub fn read_layout(layout: &str, name: Option<&str>, dir: &Path) -> Result<Layout, String> {
+    if let Some(preset) = Layout::preset(layout) {
+        return match name {
+            Some(_) => Err(format!(
+                "`layout_name` only applies to a QMK info.json, not the bundled `{layout}` layout"
+            )),
+            None => Ok(preset),
+        };
+    }
+    let info = fs::read_to_string(dir.join(layout)).map_err(|error| {
+        let presets = Layout::preset_names().collect::<Vec<_>>().join(", ");
+        format!(
+            "`layout` should be one of {presets}, or a QMK info.json, cannot read `{layout}`: {error}"
+        )
+    })?;
+    Layout::from_qmk_info(&info, name)
+        .map_err(|error| format!("invalid layout `{layout}`: {error}"))
+}
+
+/// Config read from the config file at `path` alone
+pub fn read_config(path: &Path) -> Result<Config, ConfigError> {
+    let mut config = Config::default();
+    ConfigOverrides::read(path)?.apply(&mut config);
+    Ok(config)
+}
+
+/// Config of the file at `path`, from the config files of its directories
+pub fn discover_config(path: &Path) -> Result<Config, ConfigError> {
+    let path = std::path::absolute(path).map_err(|error| ConfigError::new(path, error))?;
+    let path = normalize(&path);
+    let mut overrides = Vec::new();
+    for dir in path.ancestors().skip(1) {
+        let config_path = dir.join(CONFIG_FILE_NAME);
+        if !config_path.is_file() {
+            continue;
+        }
+        let config_overrides = ConfigOverrides::read(&config_path)?;
+        let root = config_overrides.root;
+        overrides.push(config_overrides);
+        if root {
+            break;
+        }
+    }
+    let mut config = Config::default();
+    if overrides.is_empty() {
+        let properties = editorconfig_properties(&path)
+            .map_err(|(path, error)| ConfigError::new(path, error))?;
+        if let Some(properties) = properties {
+            apply_editorconfig(&properties, &mut config);
+        }
+        return Ok(config);
+    }
+    // Further config files are overridden by closer ones
+    for config_overrides in overrides.into_iter().rev() {
+        config_overrides.apply(&mut config);
+    }
+    Ok(config)
+}
+
+/// `path` without `.` and `..` components, so that only the directories of the file are searched
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Options from `.editorconfig` properties, ignoring the values other tools could understand
+fn apply_editorconfig(properties: &BTreeMap<String, String>, config: &mut Config) {
+    let property = |name: &str| properties.get(name).map(|value| value.to_lowercase());
+    let number = |name: &str| property(name).and_then(|value| value.parse::<u32>().ok());
+    match property("indent_style").as_deref() {
+        Some("space") => config.indent_style = IndentStyle::Space,
+        Some("tab") => config.indent_style = IndentStyle::Tab,
+        _ => {}
+    }
+    // `indent_size = tab` and a missing `indent_size` both mean the tab width
+    if let Some(indent_width) = number("indent_size").or_else(|| number("tab_width")) {
+        config.indent_width = indent_width;
+    }
+    match property("end_of_line").as_deref() {
+        Some("lf") => config.end_of_line = EndOfLine::Lf,
+        Some("crlf") => config.end_of_line = EndOfLine::Crlf,
+        _ => {}
+    }
+    if let Some(max_width) = number("max_line_length").filter(|max_width| *max_width > 0) {
+        config.max_width = max_width;
+    }
+    match property("insert_final_newline").as_deref() {
+        Some("true") => config.insert_final_newline = true,
+        Some("false") => config.insert_final_newline = false,
+        _ => {}
+    }
+}
+
+/// Config from the content of a config file, resolving paths from `dir`
+pub fn parse_config(text: &str, dir: &Path) -> Result<Config, ConfigError> {
+    let mut config = Config::default();
+    ConfigOverrides::parse(text, dir)
+        .map_err(|error| ConfigError::new(CONFIG_FILE_NAME, error))?
+        .apply(&mut config);
+    Ok(config)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub msg: String,
+}
+
+impl ConfigError {
+    fn new(path: impl AsRef<Path>, msg: impl ToString) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.msg.trim_end())
+    }
+}
+
+impl Error for ConfigError {}
@@ -0,0 +1,536 @@
+//! Build and edit typed AST nodes.
+//!
+//! Syntax trees are immutable, every edit returns the edited node in a copy of its tree,
+//! sharing the untouched parts with the original one. [`AstNode::document`] gives back
+//! the edited document. Trivia around edited nodes, comments included, are kept as they are.
+//!
+//! New nodes are parsed from text, and only get the white spaces needed to keep
+//! their tokens apart, or a line break after a line comment.
+//! Laying them out is the formatter's job.
+
+use crate::{
+    ast::{
+        ArrayCell, ArrayValue, AstNode, BoolPropertyDefinition, Document, IntCell, MacroCell,
+        NodeBodyEntry, NodeDefinition, NonBoolPropertyDefinition, PhandleCell, PropertyDefinition,
+        PropertyValue, StringValue, SyntaxResult,
+    },
+    lexer::TokenKind,
+    parser::parse,
+    source::Source,
+    syntax::{GreenChild, GreenNode, GreenTokenData, GreenTrivia, SyntaxNode},
+};
+
+impl Document {
+    pub fn new(statements: impl IntoIterator<Item = NodeDefinition>) -> Self {
+        let (doc, _) = parse(&Source::new(""));
+        statements
+            .into_iter()
+            .fold(doc, |doc, node| doc.push_statement(node))
+    }
+
+    /// Insert a top level node before the `index`th statement
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of statements
+    pub fn insert_statement(&self, index: usize, node: NodeDefinition) -> Document {
+        let position = insert_position(self.syntax(), index, Some(TokenKind::EOF));
+        let green = self.syntax().green().splice_children(
+            position..position,
+            [GreenChild::Node(inserted(
+                self.syntax(),
+                position,
+                node.syntax(),
+            ))],
+        );
+        Document::cast(&self.syntax().replace_with(green)).unwrap()
+    }
+
+    pub fn push_statement(&self, node: NodeDefinition) -> Document {
+        let len = self.statements().into_iter().count();
+        self.insert_statement(len, node)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if there is no `index`th statement
+    pub fn remove_statement(&self, index: usize) -> Document {
+        let position = node_position(self.syntax(), index);
+        let green = self
+            .syntax()
+            .green()
+            .splice_children(position..position + 1, []);
+        Document::cast(&self.syntax().replace_with(green)).unwrap()
+    }
+}
+
+impl NodeDefinition {
+    /// A node named `identifier`, which is either `name` or `name@address`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `identifier` isn't a valid node identifier
+    pub fn new(
+        identifier: &str,
+        entries: impl IntoIterator<Item = impl Into<NodeBodyEntry>>,
+    ) -> Self {
+        let node: NodeDefinition = make_entry(&format!("{identifier} {{}};"));
+        node.with_entries(entries)
+    }
+
+    /// The root node `/`
+    pub fn root(entries: impl IntoIterator<Item = impl Into<NodeBodyEntry>>) -> Self {
+        let (doc, _) = parse(&Source::new("/ {};"));
+        let node = doc
+            .syntax()
+            .descendants()
+            .find_map(|node| Self::cast(&node));
+        let node = detach(&node.unwrap());
+        node.with_entries(entries)
+    }
+
+    /// Insert an entry before the `index`th entry of the node's body
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of entries
+    pub fn insert_entry(
+        &self,
+        index: usize,
+        entry: impl Into<NodeBodyEntry>,
+    ) -> SyntaxResult<Self> {
+        let entries = self.body()?.entries()?;
+        let position = insert_position(entries.syntax(), index, None);
+        let entry = entry.into();
+        let green = entries.syntax().green().splice_children(
+            position..position,
+            [GreenChild::Node(inserted(
+                entries.syntax(),
+                position,
+                entry.syntax(),
+            ))],
+        );
+        Ok(edited_ancestor(&entries.syntax().replace_with(green)))
+    }
+
+    pub fn push_entry(&self, entry: impl Into<NodeBodyEntry>) -> SyntaxResult<Self> {
+        let len = self.body()?.entries()?.entries().into_iter().count();
+        self.insert_entry(len, entry)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if there is no `index`th entry
+    pub fn remove_entry(&self, index: usize) -> SyntaxResult<Self> {
+        let entries = self.body()?.entries()?;
+        let position = node_position(entries.syntax(), index);
+        let green = entries
+            .syntax()
+            .green()
+            .splice_children(position..position + 1, []);
+        Ok(edited_ancestor(&entries.syntax().replace_with(green)))
+    }
+
+    /// Remove the node from its document, and return the edited document.
+    /// `None` if the node isn't part of a document.
+    pub fn remove(&self) -> Option<Document> {
+        remove_from_document(self.syntax())
+    }
+
+    fn with_entries(self, entries: impl IntoIterator<Item = impl Into<NodeBodyEntry>>) -> Self {
+        entries.into_iter().fold(self, |node, entry| {
+            node.push_entry(entry).expect("A new node must have a body")
+        })
+    }
+}
+
+impl PropertyDefinition {
+    /// Remove the property from its document, and return the edited document.
+    /// `None` if the property isn't part of a document.
+    pub fn remove(&self) -> Option<Document> {
+        remove_from_document(self.syntax())
+    }
+}
+
+impl BoolPropertyDefinition {
+    /// # Panics
+    ///
+    /// Panics if `name` isn't a valid property name
+    pub fn new(name: &str) -> Self {
+        make_entry(&format!("{name};"))
+    }
+}
+
+impl NonBoolPropertyDefinition {
+    /// # Panics
+    ///
+    /// Panics if `name` isn't a valid property name, or if `values` is empty
+    pub fn new(name: &str, values: impl IntoIterator<Item = impl Into<PropertyValue>>) -> Self {
+        let property: Self = make_entry(&format!("{name} = <>;"));
+        property
+            .with_values(values)
+            .expect("A new property must have values")
+    }
+
+    /// Replace all values of the property
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is empty
+    pub fn with_values(
+        &self,
+        values: impl IntoIterator<Item = impl Into<PropertyValue>>,
+    ) -> SyntaxResult<Self> {
+        let property_values = self.values()?;
+        let syntax = property_values.syntax();
+        let mut children = Vec::new();
+        for value in values {
+            if !children.is_empty() {
+                children.push(GreenChild::Token(GreenTokenData::new(
+                    TokenKind::COMMA,
+                    ",",
+                    Vec::new(),
+                    vec![space()],
+                )));
+            }
+            children.push(GreenChild::Node(value.into().syntax().green().clone()));
+        }
+        assert!(!children.is_empty(), "A property must have values");
+        // Keep the ";" and the comments after it
+        let end = syntax
+            .children()
+            .iter()
+            .position(|child| {
+                child
+                    .as_token()
+                    .is_some_and(|token| token.kind() == TokenKind::SEMICOLON)
+            })
+            .unwrap_or(syntax.children().len());
+        let green = syntax.green().splice_children(0..end, children);
+        Ok(edited_ancestor(&syntax.replace_with(green)))
+    }
+
+    /// # Panics
+    ///
+    /// Panics if there is no `index`th value
+    pub fn replace_value(
+        &self,
+        index: usize,
+        value: impl Into<PropertyValue>,
+    ) -> SyntaxResult<Self> {
+        let old = self
+            .values()?
+            .values()
+            .into_iter()
+            .nth(index)
+            .expect("Value index out of bounds");
+        let new = old.replace_with(value.into());
+        Ok(edited_ancestor(new.syntax()))
+    }
+}
+
+impl ArrayValue {
+    /// # Panics
+    ///
+    /// Panics if any cell isn't valid
+    pub fn new(cells: impl IntoIterator<Item = impl Into<ArrayCell>>) -> Self {
+        let array: Self = make_value("<>");
+        cells
+            .into_iter()
+            .fold(array, |array, cell| array.push_cell(cell))
+    }
+
+    /// Insert a cell before the `index`th cell
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of cells
+    pub fn insert_cell(&self, index: usize, cell: impl Into<ArrayCell>) -> Self {
+        let syntax = self.syntax();
+        let position = insert_position(syntax, index, Some(TokenKind::R_ANGLE));
+        let green = syntax.green().splice_children(
+            position..position,
+            [GreenChild::Node(cell.into().syntax().green().clone())],
+        );
+        Self::cast(&syntax.replace_with(separate_cells(&green))).unwrap()
+    }
+
+    pub fn push_cell(&self, cell: impl Into<ArrayCell>) -> Self {
+        let len = self.cells().into_iter().count();
+        self.insert_cell(len, cell)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if there is no `index`th cell
+    pub fn replace_cell(&self, index: usize, cell: impl Into<ArrayCell>) -> Self {
+        let old = self
+            .cells()
+            .into_iter()
+            .nth(index)
+            .expect("Cell index out of bounds");
+        let new = old.replace_with(cell.into());
+        Self::cast(&new.syntax().parent().unwrap()).unwrap()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if there is no `index`th cell
+    pub fn remove_cell(&self, index: usize) -> Self {
+        let syntax = self.syntax();
+        let position = node_position(syntax, index);
+        let green = syntax.green().splice_children(position..position + 1, []);
+        Self::cast(&syntax.replace_with(green)).unwrap()
+    }
+}
+
+impl IntCell {
+    pub fn new(value: u64) -> Self {
+        make_cell(&value.to_string())
+    }
+}
+
+impl PhandleCell {
+    /// A reference to the node labeled `label`, like `&kp`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `label` isn't a valid label
+    pub fn new(label: &str) -> Self {
+        make_cell(&format!("&{label}"))
+    }
+}
+
+impl MacroCell {
+    /// A macro, like `Q` or `RC(0,1)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `text` isn't a single macro
+    pub fn new(text: &str) -> Self {
+        make_cell(text)
+    }
+}
+
+impl StringValue {
+    /// A string containing `value`, quotes and backslashes are escaped
+    pub fn new(value: &str) -> Self {
+        let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+        make_value(&format!("\"{escaped}\""))
+    }
+}
+
+impl From<NodeDefinition> for NodeBodyEntry {
+    fn from(node: NodeDefinition) -> Self {
+        Self::Node(node)
+    }
+}
+
+impl From<PropertyDefinition> for NodeBodyEntry {
+    fn from(property: PropertyDefinition) -> Self {
+        Self::Property(property)
+    }
+}
+
+impl From<BoolPropertyDefinition> for NodeBodyEntry {
+    fn from(property: BoolPropertyDefinition) -> Self {
+        Self::Property(PropertyDefinition::Bool(property))
+    }
+}
+
+impl From<NonBoolPropertyDefinition> for NodeBodyEntry {
+    fn from(property: NonBoolPropertyDefinition) -> Self {
+        Self::Property(PropertyDefinition::NonBool(property))
+    }
+}
+
+impl From<ArrayValue> for PropertyValue {
+    fn from(array: ArrayValue) -> Self {
+        Self::Array(array)
+    }
+}
+
+impl From<StringValue> for PropertyValue {
+    fn from(string: StringValue) -> Self {
+        Self::String(string)
+    }
+}
+
+impl From<IntCell> for ArrayCell {
+    fn from(cell: IntCell) -> Self {
+        Self::Int(cell)
+    }
+}
+
+impl From<PhandleCell> for ArrayCell {
+    fn from(cell: PhandleCell) -> Self {
+        Self::Phandle(cell)
+    }
+}
+
+impl From<MacroCell> for ArrayCell {
+    fn from(cell: MacroCell) -> Self {
+        Self::Macro(cell)
+    }
+}
+
+/// Replace `old` with `new` in a copy of its tree, `new` takes over the trivia around `old`
+pub(crate) fn replace_node(old: &SyntaxNode, new: &SyntaxNode) -> SyntaxNode {
+    let old_green = old.green();
+    let green = new.green().with_boundary_trivia(
+        old_green.first_leading_trivia().to_vec(),
+        old_green.last_trailing_trivia().to_vec(),
+    );
+    old.replace_with(green)
+}
+
+fn make_entry<T: AstNode>(text: &str) -> T {
+    make(&format!("/ {{ {text} }};"), text)
+}
+
+fn make_value<T: AstNode>(text: &str) -> T {
+    make(&format!("/ {{ p = {text}; }};"), text)
+}
+
+fn make_cell<T: AstNode>(text: &str) -> T {
+    make(&format!("/ {{ p = <{text}>; }};"), text)
+}
+
+/// Parse `template`, and take the `T` node made of `text` out of it
+fn make<T: AstNode>(template: &str, text: &str) -> T {
+    let source = Source::new(template);
+    let (doc, diagnostics) = parse(&source);
+    let node = doc
+        .syntax()
+        .descendants()
+        // Skip the document and the root node of the template
+        .skip(2)
+        .find_map(|node| T::cast(&node))
+        .filter(|node| diagnostics.is_empty() && node.syntax().text() == text);
+    match node {
+        Some(node) => detach(&node),
+        None => panic!("`{text}` isn't a valid {}", std::any::type_name::<T>()),
+    }
+}
+
+/// A copy of `node` as the root of its own tree, without trivia around it
+fn detach<T: AstNode>(node: &T) -> T {
+    let green = node
+        .syntax()
+        .green()
+        .with_boundary_trivia(Vec::new(), Vec::new());
+    T::cast(&SyntaxNode::new_root(green)).unwrap()
+}
+
+/// Make sure that there is a white space after every cell but the last one,
+/// or adjacent cells would be merged when printed
+fn separate_cells(array: &GreenNode) -> GreenNode {
+    let mut array = array.clone();
+    let cells = array
+        .children()
+        .iter()
+        .enumerate()
+        .filter(|(_, child)| matches!(child, GreenChild::Node(_)))
+        .map(|(position, _)| position)
+        .collect::<Vec<_>>();
+    for pair in cells.windows(2) {
+        let (GreenChild::Node(cell), GreenChild::Node(next)) =
+            (&array.children()[pair[0]], &array.children()[pair[1]])
+        else {
+            unreachable!()
+        };
+        if cell.last_trailing_trivia().is_empty() && next.first_leading_trivia().is_empty() {
+            let cell =
+                cell.with_boundary_trivia(cell.first_leading_trivia().to_vec(), vec![space()]);
+            array = array.replace_child(pair[0], GreenChild::Node(cell));
+        }
+    }
+    array
+}
+
+fn space() -> GreenTrivia {
+    GreenTrivia::new(TokenKind::SPACE, " ")
+}
+
+/// Position of the `index`th child node among all children of `syntax`, tokens included
+fn node_position(syntax: &SyntaxNode, index: usize) -> usize {
+    syntax
+        .children()
+        .iter()
+        .enumerate()
+        .filter(|(_, child)| child.as_node().is_some())
+        .nth(index)
+        .map(|(position, _)| position)
+        .expect("Index out of bounds")
+}
+
+/// Position to insert a node before the `index`th child node of `syntax`.
+/// Nodes are appended before the `end` token if any, or after all children otherwise.
+fn insert_position(syntax: &SyntaxNode, index: usize, end: Option<TokenKind>) -> usize {
+    let children = syntax.children();
+    let node_count = children
+        .iter()
+        .filter(|child| child.as_node().is_some())
+        .count();
+    if index < node_count {
+        return node_position(syntax, index);
+    }
+    assert_eq!(index, node_count, "Index out of bounds");
+    end.and_then(|end| {
+        children
+            .iter()
+            .rposition(|child| child.as_token().is_some_and(|token| token.kind() == end))
+    })
+    .unwrap_or(children.len())
+}
+
+/// `node` to insert at `position` among the children of `syntax`,
+/// starting on a new line if the source before it ends with a line comment
+fn inserted(syntax: &SyntaxNode, position: usize, node: &SyntaxNode) -> GreenNode {
+    let green = node.green();
+    if !follows_line_comment(syntax, position) {
+        return green.clone();
+    }
+    let mut leading_trivia = vec![GreenTrivia::new(TokenKind::NEW_LINE, "\n")];
+    leading_trivia.extend(green.first_leading_trivia().iter().cloned());
+    green.with_boundary_trivia(leading_trivia, green.last_trailing_trivia().to_vec())
+}
+
+/// Whether the last token before the `position`th child of `syntax` ends with a line comment
+fn follows_line_comment(syntax: &SyntaxNode, position: usize) -> bool {
+    let previous = syntax.green().children()[..position]
+        .iter()
+        .rev()
+        .find(|child| child.has_token());
+    match previous {
+        Some(GreenChild::Token(token)) => ends_with_line_comment(token.trailing_trivia()),
+        Some(GreenChild::Node(node)) => ends_with_line_comment(node.last_trailing_trivia()),
+        None => syntax
+            .parent()
+            .is_some_and(|parent| follows_line_comment(&parent, syntax.index())),
+    }
+}
+
+fn ends_with_line_comment(trivia: &[GreenTrivia]) -> bool {
+    trivia
+        .last()
+        .is_some_and(|trivia| trivia.kind() == TokenKind::S_COMMENT)
+}
+
+/// The closest `T` containing the edited `node`
+fn edited_ancestor<T: AstNode>(node: &SyntaxNode) -> T {
+    let mut node = node.clone();
+    loop {
+        if let Some(ancestor) = T::cast(&node) {
+            return ancestor;
+        }
+        node = node.parent().expect("Edited node must have an ancestor");
+    }
+}
+
+fn remove_from_document(syntax: &SyntaxNode) -> Option<Document> {
+    let parent = syntax.parent()?;
+    let position = syntax.index();
+    let green = parent.green().splice_children(position..position + 1, []);
+    Document::cast(&parent.replace_with(green).root())
+}
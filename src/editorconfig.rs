@@ -0,0 +1,357 @@
+//! `.editorconfig` files, used when there's no `zmk-styler.toml`.
+//!
+//! Like other EditorConfig tools, the `.editorconfig` files of the directories of a file
+//! are read until one of them sets `root = true`. The properties of the sections whose glob
+//! matches the file are applied from the furthest file to the closest one, and from top
+//! to bottom within a file, so later sections override earlier ones.
+//! A property set to `unset` goes back to its default.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub(crate) const EDITORCONFIG_FILE_NAME: &str = ".editorconfig";
+
+/// Parsed `.editorconfig` file
+#[derive(Debug)]
+pub(crate) struct EditorConfig {
+    root: bool,
+    sections: Vec<Section>,
+}
+
+#[derive(Debug)]
+struct Section {
+    glob: Glob,
+    /// Properties with lowercase names, in the order they're listed
+    properties: Vec<(String, String)>,
+}
+
+impl EditorConfig {
+    /// Parse the content of a `.editorconfig` file found in `dir`
+    pub(crate) fn parse(text: &str, dir: &Path) -> Self {
+        let mut root = false;
+        let mut sections: Vec<Section> = Vec::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            if let Some(glob) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                sections.push(Section {
+                    glob: Glob::new(glob, dir),
+                    properties: Vec::new(),
+                });
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim().to_owned();
+            match sections.last_mut() {
+                Some(section) => section.properties.push((key, value)),
+                // Properties before the first section are the preamble
+                None if key == "root" => root = value.eq_ignore_ascii_case("true"),
+                None => {}
+            }
+        }
+        Self { root, sections }
+    }
+
+    /// Set the properties of the sections matching `path` into `properties`
+    fn apply(&self, path: &str, properties: &mut BTreeMap<String, String>) {
+        for section in self
+            .sections
+            .iter()
+            .filter(|section| section.glob.matches(path))
+        {
+            for (key, value) in &section.properties {
+                if value.eq_ignore_ascii_case("unset") {
+                    properties.remove(key);
+                } else {
+                    properties.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Properties of the `.editorconfig` files applying to the file at `path`,
+/// with lowercase names, and `None` if there's no `.editorconfig`.
+/// `path` should be absolute and normalized, the globs are matched against it.
+pub(crate) fn editorconfig_properties(
+    path: &Path,
+) -> Result<Option<BTreeMap<String, String>>, (PathBuf, io::Error)> {
+    let mut editorconfigs = Vec::new();
+    for dir in path.ancestors().skip(1) {
+        let editorconfig_path = dir.join(EDITORCONFIG_FILE_NAME);
+        if !editorconfig_path.is_file() {
+            continue;
+        }
+        let text = fs::read_to_string(&editorconfig_path)
+            .map_err(|error| (editorconfig_path.clone(), error))?;
+        let editorconfig = EditorConfig::parse(&text, dir);
+        let root = editorconfig.root;
+        editorconfigs.push(editorconfig);
+        if root {
+            break;
+        }
+    }
+    if editorconfigs.is_empty() {
+        return Ok(None);
+    }
+    let path = glob_path(path);
+    let mut properties = BTreeMap::new();
+    // Closer files override further ones
+    for editorconfig in editorconfigs.iter().rev() {
+        editorconfig.apply(&path, &mut properties);
+    }
+    Ok(Some(properties))
+}
+
+/// Path with `/` separators, which globs are matched against
+fn glob_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// Section glob, matched against whole paths
+#[derive(Debug)]
+pub(crate) struct Glob {
+    /// Alternatives from expanding `{a,b}`, any of them can match
+    alternatives: Vec<Vec<GlobToken>>,
+}
+
+#[derive(Debug, Clone)]
+enum GlobToken {
+    Char(char),
+    /// `?`, any character but `/`
+    AnyChar,
+    /// `*`, any characters but `/`
+    Star,
+    /// `**`, any characters
+    DoubleStar,
+    /// `[abc]`, `[a-z]` or `[!abc]`
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    /// `{1..10}`, an integer in the range
+    Integers(i64, i64),
+}
+
+impl Glob {
+    /// Glob of a section of the `.editorconfig` in `dir`. A glob without `/` matches
+    /// files in any directory below `dir`, otherwise the glob is relative to `dir`.
+    pub(crate) fn new(glob: &str, dir: &Path) -> Self {
+        let mut prefix = glob_path(dir)
+            .trim_end_matches('/')
+            .chars()
+            .map(GlobToken::Char)
+            .collect::<Vec<_>>();
+        prefix.push(GlobToken::Char('/'));
+        let prefixes = if glob.contains('/') {
+            vec![prefix]
+        } else {
+            let mut in_subdirectory = prefix.clone();
+            in_subdirectory.extend([GlobToken::DoubleStar, GlobToken::Char('/')]);
+            vec![prefix, in_subdirectory]
+        };
+        let alternatives = expand_braces(glob.strip_prefix('/').unwrap_or(glob))
+            .into_iter()
+            .flat_map(|alternative| {
+                prefixes.iter().map(move |prefix| {
+                    let mut tokens = prefix.clone();
+                    tokens.extend(alternative.iter().cloned());
+                    tokens
+                })
+            })
+            .collect();
+        Self { alternatives }
+    }
+
+    pub(crate) fn matches(&self, path: &str) -> bool {
+        let path = path.chars().collect::<Vec<_>>();
+        self.alternatives
+            .iter()
+            .any(|tokens| matches_tokens(tokens, &path))
+    }
+}
+
+/// Tokens of every alternative of `glob`
+fn expand_braces(glob: &str) -> Vec<Vec<GlobToken>> {
+    let chars = glob.chars().collect::<Vec<_>>();
+    let mut alternatives = vec![Vec::new()];
+    let mut index = 0;
+    while index < chars.len() {
+        let tokens = match chars[index] {
+            '\\' if index + 1 < chars.len() => {
+                index += 1;
+                vec![vec![GlobToken::Char(chars[index])]]
+            }
+            '*' if chars.get(index + 1) == Some(&'*') => {
+                index += 1;
+                vec![vec![GlobToken::DoubleStar]]
+            }
+            '*' => vec![vec![GlobToken::Star]],
+            '?' => vec![vec![GlobToken::AnyChar]],
+            '[' => match parse_class(&chars[index + 1..]) {
+                Some((token, length)) => {
+                    index += length;
+                    vec![vec![token]]
+                }
+                None => vec![vec![GlobToken::Char('[')]],
+            },
+            '{' => match closing_brace(&chars[index + 1..]) {
+                Some(length) => {
+                    let content = chars[index + 1..index + 1 + length]
+                        .iter()
+                        .collect::<String>();
+                    index += length + 1;
+                    brace_alternatives(&content)
+                }
+                None => vec![vec![GlobToken::Char('{')]],
+            },
+            char => vec![vec![GlobToken::Char(char)]],
+        };
+        alternatives = alternatives
+            .into_iter()
+            .flat_map(|alternative| {
+                tokens.iter().map(move |tokens| {
+                    let mut alternative = alternative.clone();
+                    alternative.extend(tokens.iter().cloned());
+                    alternative
+                })
+            })
+            .collect();
+        index += 1;
+    }
+    alternatives
+}
+
+/// Alternatives of the content of `{}`: an integer range, or comma separated globs
+fn brace_alternatives(content: &str) -> Vec<Vec<GlobToken>> {
+    if let Some((start, end)) = content.split_once("..") {
+        if let (Ok(start), Ok(end)) = (start.parse::<i64>(), end.parse::<i64>()) {
+            return vec![vec![GlobToken::Integers(start.min(end), start.max(end))]];
+        }
+    }
+    let parts = split_top_level_commas(content);
+    if parts.len() == 1 {
+        // `{single}` isn't a list of alternatives
+        let mut tokens = vec![GlobToken::Char('{')];
+        tokens.extend(content.chars().map(GlobToken::Char));
+        tokens.push(GlobToken::Char('}'));
+        return vec![tokens];
+    }
+    parts.iter().flat_map(|part| expand_braces(part)).collect()
+}
+
+/// Length of the content of braces starting after `{`, up to the matching `}`
+fn closing_brace(chars: &[char]) -> Option<usize> {
+    let mut depth = 0;
+    let mut index = 0;
+    while index < chars.len() {
+        match chars[index] {
+            '\\' => index += 1,
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(index),
+            '}' => depth -= 1,
+            _ => {}
+        }
+        index += 1;
+    }
+    None
+}
+
+fn split_top_level_commas(content: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut depth = 0;
+    let mut chars = content.chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => {
+                let part = parts.last_mut().unwrap();
+                part.push(char);
+                part.extend(chars.next());
+                continue;
+            }
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        parts.last_mut().unwrap().push(char);
+    }
+    parts
+}
+
+/// Character class starting after `[`, with the length of its content and `]`
+fn parse_class(chars: &[char]) -> Option<(GlobToken, usize)> {
+    let end = chars.iter().position(|char| *char == ']')?;
+    let mut content = &chars[..end];
+    let negated = content.first() == Some(&'!');
+    if negated {
+        content = &content[1..];
+    }
+    let mut ranges = Vec::new();
+    let mut index = 0;
+    while index < content.len() {
+        if index + 2 < content.len() && content[index + 1] == '-' {
+            ranges.push((content[index], content[index + 2]));
+            index += 3;
+        } else {
+            ranges.push((content[index], content[index]));
+            index += 1;
+        }
+    }
+    Some((GlobToken::Class { negated, ranges }, end + 1))
+}
+
+fn matches_tokens(tokens: &[GlobToken], path: &[char]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return path.is_empty();
+    };
+    match token {
+        GlobToken::Char(char) => path.first() == Some(char) && matches_tokens(rest, &path[1..]),
+        GlobToken::AnyChar => {
+            path.first().is_some_and(|char| *char != '/') && matches_tokens(rest, &path[1..])
+        }
+        GlobToken::Star => (0..=path.len())
+            .take_while(|length| *length == 0 || path[length - 1] != '/')
+            .any(|length| matches_tokens(rest, &path[length..])),
+        GlobToken::DoubleStar => {
+            (0..=path.len()).any(|length| matches_tokens(rest, &path[length..]))
+        }
+        GlobToken::Class { negated, ranges } => {
+            path.first().is_some_and(|char| {
+                *char != '/'
+                    && ranges
+                        .iter()
+                        .any(|(start, end)| (start..=end).contains(&char))
+                        != *negated
+            }) && matches_tokens(rest, &path[1..])
+        }
+        GlobToken::Integers(start, end) => {
+            let sign = usize::from(path.first() == Some(&'-'));
+            let digits = path[sign..]
+                .iter()
+                .take_while(|char| char.is_ascii_digit())
+                .count();
+            (sign + 1..=sign + digits).any(|length| {
+                let number = path[..length].iter().collect::<String>();
+                number
+                    .parse::<i64>()
+                    .is_ok_and(|number| (*start..=*end).contains(&number))
+                    && matches_tokens(rest, &path[length..])
+            })
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Checks of the formatted text against the source,
+//! so that formatting never silently loses part of a document.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{
+    ast::AstNode,
+    lexer::{Lexer, Token},
+    line_index::ColumnUnit,
+    parser::parse,
+    source::{Source, SourceIndex, SourceRange},
+    syntax::{SyntaxNode, SyntaxNodeChild, SyntaxToken},
+};
+
+/// Where the formatted text stops being equivalent to the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Divergence {
+    /// Start of the first token or comment of the source that differs
+    pub(crate) position: SourceIndex,
+    pub(crate) msg: String,
+}
+
+/// Range of the first comment of `source` that's missing from `formatted`
+pub(crate) fn missing_comment(source: &Source, formatted: &str) -> Option<SourceRange> {
+    let formatted = Source::new(formatted);
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for comment in comments(&formatted) {
+        *counts
+            .entry(comment_text(&comment, &formatted))
+            .or_default() += 1;
+    }
+    comments(source)
+        .into_iter()
+        .find(
+            |comment| match counts.get_mut(&comment_text(comment, source)) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    false
+                }
+                _ => true,
+            },
+        )
+        .map(|comment| comment.range)
+}
+
+fn comments(source: &Source) -> Vec<Token> {
+    let mut lexer = Lexer::new(source);
+    let mut comments = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        if token.is_eof() {
+            return comments;
+        }
+        if token.is_comment() {
+            comments.push(token);
+        }
+    }
+}
+
+/// Block comments are reindented, so their lines are compared without indentation
+fn comment_text(comment: &Token, source: &Source) -> String {
+    source.text(comment.range).lines().map(str::trim).join("\n")
+}
+
+/// First line of the formatted text that changes when it's formatted again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Instability {
+    /// Zero-based line of the formatted text
+    pub(crate) line: usize,
+    pub(crate) formatted: String,
+    pub(crate) reformatted: String,
+}
+
+/// First line that differs between `formatted` and `reformatted`, the result of formatting it
+pub(crate) fn instability(formatted: &str, reformatted: &str) -> Option<Instability> {
+    if formatted == reformatted {
+        return None;
+    }
+    let lines = formatted.split('\n').collect::<Vec<_>>();
+    let reformatted_lines = reformatted.split('\n').collect::<Vec<_>>();
+    let line = (0..)
+        .find(|line| lines.get(*line) != reformatted_lines.get(*line))
+        .unwrap();
+    Some(Instability {
+        line,
+        formatted: lines.get(line).copied().unwrap_or_default().to_owned(),
+        reformatted: reformatted_lines
+            .get(line)
+            .copied()
+            .unwrap_or_default()
+            .to_owned(),
+    })
+}
+
+/// First difference between the tokens, or the comments, of `source` and of `formatted`,
+/// once `formatted` is parsed again
+pub(crate) fn divergence(source: &Source, formatted: &str) -> Option<Divergence> {
+    let formatted = Source::new(formatted);
+    let (source_document, _) = parse(source);
+    let (formatted_document, diagnostics) = parse(&formatted);
+    let source_tokens = tokens(source_document.syntax());
+    let formatted_tokens = tokens(formatted_document.syntax());
+
+    let end = SourceIndex::new(source.as_str().len());
+    let formatted_end = SourceIndex::new(formatted.as_str().len());
+    let texts = |tokens: &[SyntaxToken]| {
+        tokens
+            .iter()
+            .map(|token| (token.range().start(), token.text().to_owned()))
+            .collect::<Vec<_>>()
+    };
+    let comments = |tokens: &[SyntaxToken], source: &Source| {
+        tokens
+            .iter()
+            .flat_map(|token| {
+                let mut trivia = token.leading_trivia();
+                trivia.extend(token.trailing_trivia());
+                trivia
+            })
+            .filter(Token::is_comment)
+            .map(|comment| (comment.range.start(), comment_text(&comment, source)))
+            .collect::<Vec<_>>()
+    };
+    let (position, msg) = first_difference(
+        &texts(&source_tokens),
+        &texts(&formatted_tokens),
+        (end, formatted_end),
+    )
+    .map(|(position, formatted_position, msg)| {
+        (
+            position,
+            format!("{msg}{}", at(&formatted, formatted_position)),
+        )
+    })
+    .or_else(|| {
+        first_difference(
+            &comments(&source_tokens, source),
+            &comments(&formatted_tokens, &formatted),
+            (end, formatted_end),
+        )
+        .map(|(position, formatted_position, msg)| {
+            (
+                position,
+                format!("comment {msg}{}", at(&formatted, formatted_position)),
+            )
+        })
+    })
+    .or_else(|| {
+        let diagnostic = diagnostics.first()?;
+        let msg = format!(
+            "the formatted text doesn't parse, {}{}",
+            diagnostic.msg,
+            at(&formatted, diagnostic.range.start())
+        );
+        Some((SourceIndex::default(), msg))
+    })?;
+    Some(Divergence { position, msg })
+}
+
+/// Positions of the first items that differ, in the source and in the formatted text,
+/// and what differs
+fn first_difference(
+    source: &[(SourceIndex, String)],
+    formatted: &[(SourceIndex, String)],
+    (end, formatted_end): (SourceIndex, SourceIndex),
+) -> Option<(SourceIndex, SourceIndex, String)> {
+    let index = source
+        .iter()
+        .zip(formatted)
+        .position(|((_, text), (_, formatted_text))| text != formatted_text)
+        .unwrap_or(source.len().min(formatted.len()));
+    match (source.get(index), formatted.get(index)) {
+        (Some((position, text)), Some((formatted_position, formatted_text))) => Some((
+            *position,
+            *formatted_position,
+            format!("`{text}` became `{formatted_text}`"),
+        )),
+        (Some((position, text)), None) => {
+            Some((*position, formatted_end, format!("`{text}` is missing")))
+        }
+        (None, Some((formatted_position, formatted_text))) => Some((
+            end,
+            *formatted_position,
+            format!("`{formatted_text}` was added"),
+        )),
+        (None, None) => None,
+    }
+}
+
+/// Position in the formatted text, for messages
+fn at(formatted: &Source, position: SourceIndex) -> String {
+    let position = formatted.line_index().line_col(position, ColumnUnit::Utf8);
+    format!(
+        " (line {}, column {} of the formatted text)",
+        position.line + 1,
+        position.col + 1
+    )
+}
+
+/// Tokens of `node`, in the order of the source
+fn tokens(node: &SyntaxNode) -> Vec<SyntaxToken> {
+    let mut tokens = Vec::new();
+    for child in node.children() {
+        match child {
+            SyntaxNodeChild::Token(token) => tokens.push(token),
+            SyntaxNodeChild::Tree(node) => tokens.extend(self::tokens(&node)),
+        }
+    }
+    tokens
+}
@@ -0,0 +1,212 @@
+//! Comments are attached to the nodes of the syntax tree before formatting, like Prettier does.
+//! The comments between two tokens belong to the nodes around them, children of the
+//! smallest node containing both tokens:
+//! - a comment on a line of its own leads the node after it, or else trails the node before it,
+//! - another comment trails the node before it, or else leads the node after it,
+//! - a comment with no node around it, like in an empty body, dangles in the containing node.
+//!
+//! Comments on the line of an opening `{` or `<` dangle too, to stay after it.
+//!
+//! Comments after a comma stay with the value before it.
+//! Comments inside nodes written as they are, like a label or a cell, aren't attached,
+//! they're written with the node's text.
+
+use std::collections::HashMap;
+
+use crate::{
+    ast::{AstNode, Document},
+    lexer::{Token, TokenKind},
+    source::SourceRange,
+    syntax::{SyntaxKind, SyntaxNode, SyntaxNodeChild, SyntaxToken},
+};
+
+/// Comments of the nodes, each with the whitespace and line breaks before it
+#[derive(Default)]
+pub(crate) struct Comments {
+    /// Range of the source being formatted, when formatting only a part of it.
+    /// The comments before and after it are left as they are.
+    pub(crate) range: Option<SourceRange>,
+    nodes: HashMap<(SyntaxKind, SourceRange), NodeComments>,
+}
+
+#[derive(Default)]
+struct NodeComments {
+    leading: Vec<Token>,
+    trailing: Vec<Token>,
+    dangling: Vec<Token>,
+}
+
+enum Placement {
+    Leading,
+    Trailing,
+    Dangling,
+}
+
+impl Comments {
+    pub(crate) fn new(document: &Document) -> Self {
+        let mut comments = Comments::default();
+        let mut tokens = Vec::new();
+        collect_tokens(
+            document.syntax(),
+            &mut vec![document.syntax().clone()],
+            &mut tokens,
+        );
+        let mut previous: Option<&(SyntaxToken, Vec<SyntaxNode>)> = None;
+        let mut before_previous = None;
+        for next in &tokens {
+            let mut gap = previous.map_or_else(Vec::new, |(token, _)| token.trailing_trivia());
+            gap.extend(next.0.leading_trivia());
+            if gap.iter().any(Token::is_comment) {
+                comments.attach(gap, previous, before_previous, next);
+            }
+            before_previous = previous;
+            previous = Some(next);
+        }
+        comments
+    }
+
+    pub(crate) fn leading<T: AstNode>(&self, node: &T) -> Vec<Token> {
+        if self
+            .range
+            .is_some_and(|range| range.start() == node.range().start())
+        {
+            return Vec::new();
+        }
+        self.get(node)
+            .map_or_else(Vec::new, |comments| comments.leading.clone())
+    }
+
+    pub(crate) fn trailing<T: AstNode>(&self, node: &T) -> Vec<Token> {
+        if self
+            .range
+            .is_some_and(|range| range.end() == node.range().end())
+        {
+            return Vec::new();
+        }
+        self.get(node)
+            .map_or_else(Vec::new, |comments| comments.trailing.clone())
+    }
+
+    /// Comments inside the node, with no child node to attach them to
+    pub(crate) fn dangling<T: AstNode>(&self, node: &T) -> Vec<Token> {
+        self.get(node)
+            .map_or_else(Vec::new, |comments| comments.dangling.clone())
+    }
+
+    fn get<T: AstNode>(&self, node: &T) -> Option<&NodeComments> {
+        self.nodes.get(&key(node.syntax()))
+    }
+
+    /// Attach the comments of the trivia between `previous` and `next`,
+    /// tokens with the nodes containing them
+    fn attach(
+        &mut self,
+        gap: Vec<Token>,
+        previous: Option<&(SyntaxToken, Vec<SyntaxNode>)>,
+        before_previous: Option<&(SyntaxToken, Vec<SyntaxNode>)>,
+        next: &(SyntaxToken, Vec<SyntaxNode>),
+    ) {
+        let previous_ancestors = previous.map_or(&next.1[..1], |(_, ancestors)| ancestors);
+        let depth = previous_ancestors
+            .iter()
+            .zip(&next.1)
+            .take_while(|(previous, next)| key(previous) == key(next))
+            .count();
+        let enclosing = &next.1[depth - 1];
+        if is_verbatim(enclosing.kind()) {
+            return;
+        }
+        let mut preceding = previous_ancestors.get(depth);
+        if previous.is_some_and(|(token, _)| token.kind() == TokenKind::COMMA) {
+            preceding = before_previous.and_then(|(_, ancestors)| ancestors.get(depth));
+        }
+        let following = next.1.get(depth);
+        let after_opening = previous.is_some_and(|(token, _)| {
+            matches!(token.kind(), TokenKind::L_CURLY | TokenKind::L_ANGLE)
+        });
+
+        let mut piece = Vec::new();
+        let mut owner = None;
+        // Comments at the start of the document are on a line of their own
+        let mut own_line = previous.is_none();
+        for token in gap {
+            own_line |= token.is_newline();
+            let is_comment = token.is_comment();
+            piece.push(token);
+            if !is_comment {
+                continue;
+            }
+            let placement = match (preceding, following) {
+                (_, Some(following)) if own_line => (following, Placement::Leading),
+                (Some(preceding), _) => (preceding, Placement::Trailing),
+                (None, Some(following)) if !after_opening => (following, Placement::Leading),
+                (None, _) => (enclosing, Placement::Dangling),
+            };
+            self.push(&placement, std::mem::take(&mut piece));
+            owner = Some(placement);
+            own_line = false;
+        }
+        // Line breaks after the last comment
+        if let Some(owner) = owner {
+            self.push(&owner, piece);
+        }
+    }
+
+    fn push(&mut self, (node, placement): &(&SyntaxNode, Placement), trivia: Vec<Token>) {
+        let comments = self.nodes.entry(key(node)).or_default();
+        match placement {
+            Placement::Leading => comments.leading.extend(trivia),
+            Placement::Trailing => comments.trailing.extend(trivia),
+            Placement::Dangling => comments.dangling.extend(trivia),
+        }
+    }
+}
+
+fn key(node: &SyntaxNode) -> (SyntaxKind, SourceRange) {
+    (node.kind(), node.range())
+}
+
+/// Tokens of `node`, with the nodes containing them from the document down,
+/// leaving out the lists whose items are the children of their parent
+fn collect_tokens(
+    node: &SyntaxNode,
+    ancestors: &mut Vec<SyntaxNode>,
+    tokens: &mut Vec<(SyntaxToken, Vec<SyntaxNode>)>,
+) {
+    for child in node.children() {
+        match child {
+            SyntaxNodeChild::Token(token) => tokens.push((token, ancestors.clone())),
+            SyntaxNodeChild::Tree(child) if is_list(child.kind()) => {
+                collect_tokens(&child, ancestors, tokens)
+            }
+            SyntaxNodeChild::Tree(child) => {
+                ancestors.push(child.clone());
+                collect_tokens(&child, ancestors, tokens);
+                ancestors.pop();
+            }
+        }
+    }
+}
+
+fn is_list(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::NodeBodyEntries | SyntaxKind::PropertyValues
+    )
+}
+
+/// Nodes written as they are, comments inside them included
+fn is_verbatim(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::Label
+            | SyntaxKind::RootNodeIdentifier
+            | SyntaxKind::NodeName
+            | SyntaxKind::NodeAddress
+            | SyntaxKind::PropertyName
+            | SyntaxKind::IntCell
+            | SyntaxKind::PhandleCell
+            | SyntaxKind::MacroCell
+            | SyntaxKind::StringValue
+    )
+}
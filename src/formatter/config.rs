@@ -1,10 +1,89 @@
-#[derive(Debug)]
-pub(crate) struct Config {
-    pub(crate) indent_width: u32,
+use serde::Deserialize;
+
+use crate::layout::Layout;
+
+/// Formatting options
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Width of an indentation level, and of a tab when measuring lines
+    pub indent_width: u32,
+    pub indent_style: IndentStyle,
+    /// Width the writer tries to keep lines under, by breaking groups that don't fit
+    pub max_width: u32,
+    /// Lay out the bindings of keymap layers as a grid
+    pub bindings_grid: bool,
+    /// Physical layout the bindings grid follows.
+    /// Without it, or when a layer doesn't have a binding for every key,
+    /// the rows of the grid follow the user's line breaks.
+    pub layout: Option<Layout>,
+    /// Put the cells of matrix transform maps at their `RC()` column,
+    /// leaving blanks for the matrix positions without a key
+    pub pad_matrix_transform: bool,
+    pub end_of_line: EndOfLine,
+    /// End the file with exactly one line break
+    pub insert_final_newline: bool,
+    /// Parse the formatted text again, and refuse it unless it has
+    /// the tokens and the comments of the source
+    pub verify: bool,
+    /// Format the formatted text again, and refuse it unless it stays the same
+    pub check_idempotency: bool,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Self { indent_width: 4 }
+        Self {
+            indent_width: 4,
+            indent_style: IndentStyle::Space,
+            max_width: 100,
+            bindings_grid: true,
+            layout: None,
+            pad_matrix_transform: false,
+            end_of_line: EndOfLine::Auto,
+            insert_final_newline: false,
+            verify: false,
+            check_idempotency: false,
+        }
+    }
+}
+
+/// What indentation levels are written with.
+/// Alignment past the indentation, like the columns of grids,
+/// is always made of spaces, so it doesn't depend on the width of tabs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndentStyle {
+    /// `indent_width` spaces per level
+    Space,
+    /// A tab per level
+    Tab,
+}
+
+/// Line breaks written in the formatted file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EndOfLine {
+    /// The line break of the first line of the source, or `\n` if there's none
+    Auto,
+    Lf,
+    Crlf,
+}
+
+impl EndOfLine {
+    /// Line breaks to write for `source`
+    pub(crate) fn detect(self, source: &str) -> Self {
+        match self {
+            EndOfLine::Auto => match source.find('\n') {
+                Some(new_line) if source[..new_line].ends_with('\r') => EndOfLine::Crlf,
+                _ => EndOfLine::Lf,
+            },
+            end_of_line => end_of_line,
+        }
+    }
+
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            EndOfLine::Auto | EndOfLine::Lf => "\n",
+            EndOfLine::Crlf => "\r\n",
+        }
     }
 }
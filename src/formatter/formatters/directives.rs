@@ -0,0 +1,95 @@
+//! Comments turning formatting off for parts of a document:
+//! - `// zmk-styler: ignore` before a node or property keeps it as it's written.
+//! - `// zmk-styler: off` before a node or property keeps it, and the ones after it,
+//!   as they're written, up to the one with `// zmk-styler: on` before it,
+//!   or the end of the node.
+//!
+//! Comments around the kept entries are still formatted.
+
+use crate::{
+    ast::AstNode,
+    formatter::{
+        ir,
+        rules::{format_leading_trivia, format_trailing_trivia, list},
+        Format, FormatContext,
+    },
+    lexer::Token,
+    source::{Source, SourceRange},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Directive {
+    Off,
+    On,
+    Ignore,
+}
+
+/// Entries to format, and runs of entries to keep as they're written
+pub(super) enum Chunk<T> {
+    Format(T),
+    Verbatim(T, T),
+}
+
+/// Split `entries` into the ones to format and the ones the directives keep
+pub(super) fn chunks<T: AstNode + Clone>(
+    entries: impl IntoIterator<Item = T>,
+    f: &FormatContext,
+) -> Vec<Chunk<T>> {
+    let mut chunks = Vec::new();
+    // First and last entries since `off`
+    let mut off: Option<(T, T)> = None;
+    for entry in entries {
+        // Directives before the range being formatted still apply
+        let trivia = entry
+            .syntax()
+            .first_token()
+            .map_or_else(Vec::new, |token| token.leading_trivia());
+        let directive = directive(&trivia, f.source);
+        match (&mut off, directive) {
+            (Some(_), Some(Directive::On)) => {
+                let (first, last) = off.take().unwrap();
+                chunks.push(Chunk::Verbatim(first, last));
+                chunks.push(Chunk::Format(entry));
+            }
+            (Some((_, last)), _) => *last = entry,
+            (None, Some(Directive::Off)) => off = Some((entry.clone(), entry)),
+            (None, Some(Directive::Ignore)) => chunks.push(Chunk::Verbatim(entry.clone(), entry)),
+            (None, _) => chunks.push(Chunk::Format(entry)),
+        }
+    }
+    if let Some((first, last)) = off {
+        chunks.push(Chunk::Verbatim(first, last));
+    }
+    chunks
+}
+
+/// Source from the first entry to the last one, with the comments around them formatted
+pub(super) fn format_verbatim<T: AstNode>(first: &T, last: &T, f: &FormatContext) -> Format {
+    let range = SourceRange::new(first.range().start(), last.range().end());
+    list([
+        format_leading_trivia(f.comments.leading(first), f.source),
+        ir::text(f.source.text(range)),
+        format_trailing_trivia(f.comments.trailing(last), f.source),
+    ])
+}
+
+/// Last directive in the comments of `trivia`
+fn directive(trivia: &[Token], source: &Source) -> Option<Directive> {
+    trivia
+        .iter()
+        .filter(|token| token.is_comment())
+        .filter_map(|token| {
+            let comment = source.text(token.range);
+            let comment = match comment.strip_prefix("//") {
+                Some(comment) => comment,
+                None => comment.strip_prefix("/*")?.strip_suffix("*/")?,
+            };
+            match comment.trim().strip_prefix("zmk-styler:")?.trim() {
+                "off" => Some(Directive::Off),
+                "on" => Some(Directive::On),
+                "ignore" => Some(Directive::Ignore),
+                _ => None,
+            }
+        })
+        .last()
+}
@@ -1,18 +1,33 @@
 use crate::{
-    ast::{Document, Statement},
-    formatter::{rules::list, Format, FormatContext, FormatResult},
+    ast::{Document, MissingSyntax, Statement},
+    formatter::{
+        comments::Comments,
+        rules::{format_leading_trivia, list},
+        Format, FormatContext, FormatResult,
+    },
+    layout::Layout,
 };
 
-use super::node::format_node;
+use super::{
+    directives::{chunks, format_verbatim, Chunk},
+    node::format_node,
+};
 
 pub(crate) fn format_document(document: Document, f: &mut FormatContext) -> FormatResult {
-    Ok(list(
-        document
-            .statements()
-            .into_iter()
-            .map(|statement| format_statement(statement, f))
-            .collect::<Result<Vec<Format>, ()>>()?,
-    ))
+    detect_layout(&document, f);
+    f.comments = Comments::new(&document);
+    let statements = chunks(document.statements(), f)
+        .into_iter()
+        .map(|chunk| match chunk {
+            Chunk::Format(statement) => format_statement(statement, f),
+            Chunk::Verbatim(first, last) => Ok(format_verbatim(&first, &last, f)),
+        })
+        .collect::<Result<Vec<Format>, MissingSyntax>>()?;
+    Ok(list([
+        list(statements),
+        // Comments of a document without statements
+        format_leading_trivia(f.comments.dangling(&document), f.source),
+    ]))
 }
 
 fn format_statement(statement: Statement, f: &mut FormatContext) -> FormatResult {
@@ -20,3 +35,10 @@ fn format_statement(statement: Statement, f: &mut FormatContext) -> FormatResult
         Statement::Node(node) => format_node(node, f),
     }
 }
+
+/// Use the layout of the document's matrix transform, unless one is configured
+pub(super) fn detect_layout(document: &Document, f: &mut FormatContext) {
+    if f.layout.is_none() {
+        f.layout = Layout::from_matrix_transform(document);
+    }
+}
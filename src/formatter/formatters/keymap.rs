@@ -0,0 +1,291 @@
+//! The bindings of keymap layers, the child nodes of a `zmk,keymap` node,
+//! are laid out as a grid. Keys are placed on the grid by the configured physical
+//! layout, or the one of the document's `zmk,matrix-transform`, or else rows follow
+//! the user's line breaks. Every column is padded
+//! to its widest binding across all the layers of the keymap.
+//!
+//! Comments on the lines before a binding are written before its row, line comments
+//! after it at the end of its row, and the other ones next to it.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    ast::{
+        ArrayCell, ArrayValue, AstNode, NodeDefinition, NonBoolPropertyDefinition,
+        PropertyDefinition, PropertyValue,
+    },
+    formatter::{
+        rules::{
+            align, format_block_comment, format_leading_trivia, format_trailing_trivia, group,
+            indent, list, new_line, pair, tag,
+        },
+        Format, FormatContext,
+    },
+    layout::KeyPosition,
+    lexer::Token,
+    syntax::SyntaxNode,
+};
+
+/// Format the value of `prop` as a grid, if it's the bindings of a keymap layer
+pub(super) fn format_layer_bindings(
+    prop: &NonBoolPropertyDefinition,
+    f: &FormatContext,
+) -> Option<Format> {
+    if !f.config.bindings_grid || !is_named(prop, "bindings") {
+        return None;
+    }
+    let layer = parent_node(prop.syntax())?;
+    let keymap = parent_node(layer.syntax()).filter(|node| node.is_compatible("zmk,keymap"))?;
+    let array = single_array(prop)?;
+    let keys = layer_keys(&array, f);
+    if keys.is_empty() {
+        return None;
+    }
+    let widths = column_widths(&keymap, f);
+    let mut rows: BTreeMap<usize, Vec<(usize, GridCell)>> = BTreeMap::new();
+    for (KeyPosition { row, column }, binding) in keys {
+        rows.entry(row).or_default().push((column, binding));
+    }
+    Some(format_grid(&array, rows.into_values(), &widths, f))
+}
+
+/// A binding, or a cell of a matrix transform map, with the comments around it
+#[derive(Default)]
+pub(super) struct GridCell {
+    /// Cells, with the comments on their lines in between
+    formats: Vec<Format>,
+    /// Width of the cells without the comments between them, which don't widen the column
+    pub(super) width: usize,
+    /// Width of the last line of the cells and comments, to pad them to their column
+    line_width: usize,
+    /// Comments on the lines before the cell
+    comments_before: Vec<Token>,
+    /// Line comments after the cell
+    comments_after: Vec<Token>,
+    /// Comments on the lines after the cell, before the end of the array
+    comments_below: Vec<Token>,
+}
+
+impl GridCell {
+    /// Add `cell` and its comments
+    pub(super) fn push<T: AstNode>(&mut self, cell: &T, f: &FormatContext) {
+        let leading = f.comments.leading(cell);
+        // Comments before the last line break are on lines of their own
+        let own_lines = leading
+            .iter()
+            .rposition(|token| token.is_newline())
+            .unwrap_or(0);
+        for (index, token) in leading.into_iter().enumerate() {
+            if index < own_lines && token.is_comment() {
+                self.comments_before.push(token);
+            } else if token.is_comment() {
+                self.push_text(f.source.text(token.range));
+            }
+        }
+        let text = f.source.text(cell.range());
+        if self.width > 0 {
+            self.width += 1;
+        }
+        self.width += text.chars().count();
+        self.push_text(text);
+        for token in f.comments.trailing(cell) {
+            if token.is_newline() || !self.comments_below.is_empty() {
+                self.comments_below.push(token);
+            } else if token.is_single_line_comment() {
+                self.comments_after.push(token);
+            } else if token.is_block_comment() {
+                self.push_text(f.source.text(token.range));
+            }
+        }
+    }
+
+    /// Add a cell or a comment, a block comment spanning lines being aligned to the cell
+    fn push_text(&mut self, text: &str) {
+        if !self.formats.is_empty() {
+            self.formats.push(tag(" "));
+            self.line_width += 1;
+        }
+        match text.rsplit_once('\n') {
+            Some((_, last_line)) => {
+                self.formats.push(format_block_comment(text));
+                // Continuation lines start with a space before the `*`
+                self.line_width = 1 + last_line.trim_start().chars().count();
+            }
+            None => {
+                self.formats.push(tag(text));
+                self.line_width += text.chars().count();
+            }
+        }
+    }
+}
+
+/// Array with a line for every row of cells, each cell at its column
+pub(super) fn format_grid(
+    array: &ArrayValue,
+    rows: impl IntoIterator<Item = Vec<(usize, GridCell)>>,
+    widths: &[usize],
+    f: &FormatContext,
+) -> Format {
+    group([
+        tag("<"),
+        // Comments after `<`
+        format_trailing_trivia(f.comments.dangling(array), f.source),
+        indent(rows.into_iter().map(|row| format_row(row, widths, f))),
+        new_line(),
+        tag(">"),
+    ])
+}
+
+/// Comments before the cells of the row, and the cells padded to the width of their column,
+/// with columns without a cell written as blanks
+fn format_row(mut row: Vec<(usize, GridCell)>, widths: &[usize], f: &FormatContext) -> Format {
+    row.sort_by_key(|(column, _)| *column);
+    let mut comments_before = Vec::new();
+    let mut comments_after = Vec::new();
+    let mut comments_below = Vec::new();
+    // Gaps between the keys of the layout are as wide as the narrowest column
+    let gap_width = widths.iter().copied().filter(|width| *width > 0).min();
+    let mut line = Vec::new();
+    // Spaces before the next cell, to its column
+    let mut padding = 0;
+    let mut next_column = 0;
+    for (column, binding) in row {
+        for width in &widths[next_column..column] {
+            padding += match width {
+                0 => gap_width.unwrap_or(0),
+                width => *width,
+            } + 1;
+        }
+        line.push(tag(" ".repeat(padding)));
+        line.push(align(binding.formats));
+        padding = widths[column].saturating_sub(binding.line_width) + 1;
+        next_column = column + 1;
+        comments_before.extend(binding.comments_before);
+        comments_after.extend(binding.comments_after);
+        comments_below.extend(binding.comments_below);
+    }
+    for comment in comments_after {
+        line.push(tag(" ".repeat(padding)));
+        line.push(tag(f.source.text(comment.range)));
+        padding = 1;
+    }
+    let comments_before = comments_before.into_iter().map(|comment| {
+        let comment = f.source.text(comment.range);
+        if comment.starts_with("/*") {
+            pair(new_line(), format_block_comment(comment))
+        } else {
+            pair(new_line(), tag(comment))
+        }
+    });
+    list(comments_before.chain([
+        new_line(),
+        list(line),
+        format_leading_trivia(comments_below, f.source),
+    ]))
+}
+
+/// Widest binding of every column, across all the layers of `keymap`
+fn column_widths(keymap: &NodeDefinition, f: &FormatContext) -> Vec<usize> {
+    let mut widths = Vec::new();
+    let bindings = keymap.child_nodes().flat_map(|layer| {
+        child_properties(&layer)
+            .filter(|prop| is_named(prop, "bindings"))
+            .filter_map(|prop| single_array(&prop))
+            .collect::<Vec<_>>()
+    });
+    for bindings in bindings {
+        for (KeyPosition { column, .. }, binding) in layer_keys(&bindings, f) {
+            if widths.len() <= column {
+                widths.resize(column + 1, 0);
+            }
+            widths[column] = widths[column].max(binding.width);
+        }
+    }
+    widths
+}
+
+/// Bindings of the layer with the position of their key
+fn layer_keys(array: &ArrayValue, f: &FormatContext) -> Vec<(KeyPosition, GridCell)> {
+    let rows = binding_rows(array, f);
+    let count = rows.iter().map(Vec::len).sum::<usize>();
+    match &f.layout {
+        Some(layout) if layout.keys().len() == count => layout
+            .keys()
+            .iter()
+            .copied()
+            .zip(rows.into_iter().flatten())
+            .collect(),
+        _ => rows
+            .into_iter()
+            .enumerate()
+            .flat_map(|(row, bindings)| {
+                bindings
+                    .into_iter()
+                    .enumerate()
+                    .map(move |(column, binding)| (KeyPosition { row, column }, binding))
+            })
+            .collect(),
+    }
+}
+
+/// Bindings of the array split into rows where the user broke lines.
+/// A binding is a phandle followed by its parameters, or a lone cell.
+fn binding_rows(array: &ArrayValue, f: &FormatContext) -> Vec<Vec<GridCell>> {
+    let mut rows: Vec<Vec<GridCell>> = Vec::new();
+    let mut in_binding = false;
+    for cell in array.cells() {
+        let is_phandle = matches!(cell, ArrayCell::Phandle(_));
+        let starts_row = rows.is_empty()
+            || cell
+                .syntax()
+                .first_token()
+                .is_some_and(|token| token.leading_trivia().iter().any(Token::is_newline));
+        if starts_row {
+            rows.push(Vec::new());
+        }
+        let row = rows.last_mut().unwrap();
+        match row.last_mut() {
+            Some(binding) if in_binding && !is_phandle && !starts_row => binding.push(&cell, f),
+            _ => {
+                let mut binding = GridCell::default();
+                binding.push(&cell, f);
+                row.push(binding);
+            }
+        }
+        in_binding |= is_phandle;
+    }
+    rows
+}
+
+pub(super) fn single_array(prop: &NonBoolPropertyDefinition) -> Option<ArrayValue> {
+    let values = prop.values().ok()?;
+    let mut values = values.values().into_iter();
+    match (values.next(), values.next()) {
+        (Some(PropertyValue::Array(array)), None) => Some(array),
+        _ => None,
+    }
+}
+
+pub(super) fn is_named(prop: &NonBoolPropertyDefinition, name: &str) -> bool {
+    prop.name()
+        .is_ok_and(|prop_name| prop_name.syntax().text() == name)
+}
+
+/// The node whose body contains `syntax`
+pub(super) fn parent_node(syntax: &SyntaxNode) -> Option<NodeDefinition> {
+    let mut ancestor = syntax.parent();
+    while let Some(node) = ancestor {
+        if let Some(node) = NodeDefinition::cast(&node) {
+            return Some(node);
+        }
+        ancestor = node.parent();
+    }
+    None
+}
+
+fn child_properties(node: &NodeDefinition) -> impl Iterator<Item = NonBoolPropertyDefinition> {
+    node.properties().filter_map(|prop| match prop {
+        PropertyDefinition::NonBool(prop) => Some(prop),
+        PropertyDefinition::Bool(_) => None,
+    })
+}
@@ -1,5 +1,9 @@
+mod directives;
 mod document;
+mod keymap;
 mod node;
 mod property;
+mod range;
 
 pub(crate) use document::format_document;
+pub(crate) use range::format_range;
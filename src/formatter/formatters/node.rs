@@ -1,76 +1,120 @@
 use crate::{
-    ast::{
-        AstNode, Label, NodeBody, NodeBodyEntries, NodeBodyEntry, NodeDefinition, NodeIdentifier,
-    },
+    ast::{Label, NodeBody, NodeBodyEntries, NodeBodyEntry, NodeDefinition, NodeIdentifier},
     formatter::{
         ir::{text_break, TextBreakKind},
         rules::{
-            format_leading_trivia, format_trailing_trivia, group, list, nil, pair, space, tag, text,
+            format_leading_trivia, format_trailing_trivia, group, if_break, indent, list, nil,
+            space, tag, text,
         },
         Format, FormatContext, FormatResult,
     },
+    lexer::Token,
 };
 
-use super::property::format_property;
+use super::{
+    directives::{chunks, format_verbatim, Chunk},
+    property::format_property,
+};
 
 pub(crate) fn format_node(node: NodeDefinition, f: &mut FormatContext) -> FormatResult {
     let label = node.label();
     let identifier = node.identifier()?;
     let body = node.body()?;
+    // Comments between the identifier and `{`, after the space between them
+    let mut before_body = f.comments.trailing(&identifier);
+    before_body.extend(f.comments.leading(&body));
     Ok(list([
-        format_leading_trivia(f.trivia.leading_trivia(node.range()), f.source),
-        label.map_or(nil(), |label| format_label(label, f)),
+        format_leading_trivia(f.comments.leading(&node), f.source),
+        label.map_or(nil(), |label| format_label(label, &identifier, f)),
         format_identifier(identifier, f)?,
         space(),
+        format_leading_trivia(before_body, f.source),
         format_node_body(body, f)?,
-        format_trailing_trivia(f.trivia.trailing_trivia(node.range()), f.source),
+        format_trailing_trivia(f.comments.trailing(&node), f.source),
     ]))
 }
 
-fn format_label(label: Label, f: &FormatContext) -> Format {
+fn format_label(label: Label, identifier: &NodeIdentifier, f: &FormatContext) -> Format {
     // TODO:: format label text and ":" separately to prevent comments
     // and whitespaces in between
-    pair(text(&label, f.source), space())
+    // Comments between the label and the identifier, after the space between them
+    let mut trivia = f.comments.trailing(&label);
+    trivia.extend(f.comments.leading(identifier));
+    list([
+        text(&label, f.source),
+        space(),
+        format_leading_trivia(trivia, f.source),
+    ])
 }
 
 fn format_identifier(identifier: NodeIdentifier, f: &FormatContext) -> FormatResult {
     let format = match identifier {
         NodeIdentifier::Root(_) => tag("/"),
-        NodeIdentifier::NonRoot(identifier) => match identifier.address() {
-            Some(address) => list([
-                text(&identifier.name()?, f.source),
-                tag("@"),
-                text(&address, f.source),
-            ]),
-            None => text(&identifier.name()?, f.source),
-        },
+        NodeIdentifier::NonRoot(identifier) => {
+            let name = identifier.name()?;
+            match identifier.address() {
+                Some(address) => {
+                    let mut trivia = f.comments.trailing(&name);
+                    trivia.extend(f.comments.leading(&address));
+                    list([
+                        text(&name, f.source),
+                        format_trailing_trivia(trivia, f.source),
+                        // `@` and the address
+                        text(&address, f.source),
+                    ])
+                }
+                None => text(&name, f.source),
+            }
+        }
     };
     Ok(format)
 }
 
 fn format_node_body(body: NodeBody, f: &mut FormatContext) -> FormatResult {
+    let r_curly = body.r_curly()?;
+    // Comments after `{` or in an empty body, and the ones between `}` and `;`
+    let (inside, after_r_curly): (Vec<_>, Vec<_>) = f
+        .comments
+        .dangling(&body)
+        .into_iter()
+        .partition(|token| token.range.start() < r_curly.range().start());
+    let entries = body.entries()?;
+    // `{ /* comment */ }` on one line
+    let space_before_close =
+        if entries.entries().into_iter().next().is_none() && inside.iter().any(Token::is_comment) {
+            if_break(nil(), space())
+        } else {
+            nil()
+        };
     Ok(list([
         group([
             tag("{"),
+            indent([format_trailing_trivia(inside, f.source)]),
             text_break(0, TextBreakKind::Open),
-            format_node_body_entries(body.entries()?, f)?,
-            format_leading_trivia(f.trivia.leading_trivia(body.r_curly()?.range), f.source),
+            format_node_body_entries(entries, f)?,
+            space_before_close,
             text_break(0, TextBreakKind::Close),
             tag("}"),
         ]),
+        format_trailing_trivia(after_r_curly, f.source),
         tag(";"),
     ]))
 }
 
 fn format_node_body_entries(entries: NodeBodyEntries, f: &mut FormatContext) -> FormatResult {
     let mut formatted = Vec::new();
-    for entry in entries.into_iter() {
+    for chunk in chunks(entries, f) {
         let sep = text_break(0, TextBreakKind::NewLine);
-        formatted.push(match entry {
-            NodeBodyEntry::Node(node) => format_node(node, f)?,
-            NodeBodyEntry::Property(property) => format_property(property, f)?,
-        });
+        formatted.push(format_entry(chunk, f)?);
         formatted.push(sep);
     }
     Ok(list(formatted))
 }
+
+pub(super) fn format_entry(chunk: Chunk<NodeBodyEntry>, f: &mut FormatContext) -> FormatResult {
+    match chunk {
+        Chunk::Format(NodeBodyEntry::Node(node)) => format_node(node, f),
+        Chunk::Format(NodeBodyEntry::Property(property)) => format_property(property, f),
+        Chunk::Verbatim(first, last) => Ok(format_verbatim(&first, &last, f)),
+    }
+}
@@ -4,12 +4,19 @@ use crate::{
         PropertyDefinition, PropertyValue, PropertyValues, StringValue,
     },
     formatter::{
+        ir::TextBreakKind,
         rules::{
-            format_leading_trivia, format_trailing_trivia, list, pair, separated_list, space, tag,
-            text,
+            fill, format_leading_trivia, format_trailing_trivia, group, if_break, indent, list,
+            nil, on_own_lines, pair, separated_list, space, tag, text, text_break,
         },
         Format, FormatContext, FormatResult,
     },
+    layout::matrix_transform_lines,
+    lexer::Token,
+};
+
+use super::keymap::{
+    format_grid, format_layer_bindings, is_named, parent_node, single_array, GridCell,
 };
 
 pub(super) fn format_property(prop: PropertyDefinition, f: &mut FormatContext) -> FormatResult {
@@ -20,32 +27,156 @@ pub(super) fn format_property(prop: PropertyDefinition, f: &mut FormatContext) -
 }
 
 fn format_bool_property(prop: BoolPropertyDefinition, f: &FormatContext) -> FormatResult {
-    Ok(pair(text(&prop.name()?, f.source), tag(";")))
+    let name = prop.name()?;
+    Ok(list([
+        format_leading_trivia(f.comments.leading(&prop), f.source),
+        text(&name, f.source),
+        format_trailing_trivia(f.comments.trailing(&name), f.source),
+        tag(";"),
+        format_trailing_trivia(f.comments.trailing(&prop), f.source),
+    ]))
 }
 
 fn format_non_bool_property(
     prop: NonBoolPropertyDefinition,
     f: &mut FormatContext,
 ) -> FormatResult {
+    let name = prop.name()?;
+    let grid = format_layer_bindings(&prop, f).or_else(|| format_matrix_transform_map(&prop, f));
     Ok(list([
-        format_leading_trivia(f.trivia.leading_trivia(prop.range()), f.source),
-        text(&prop.name()?, f.source),
-        space(),
-        tag("="),
-        space(),
-        format_property_values(prop.values()?, f),
+        format_leading_trivia(f.comments.leading(&prop), f.source),
+        text(&name, f.source),
+        format_trailing_trivia(f.comments.trailing(&name), f.source),
+        match (grid, single_array(&prop)) {
+            (Some(grid), Some(array)) => format_equal_value(
+                f.comments.leading(&array),
+                list([
+                    grid,
+                    format_trailing_trivia(f.comments.trailing(&array), f.source),
+                ]),
+                f,
+            ),
+            _ => format_property_values(prop.values()?, f),
+        },
         tag(";"),
-        format_trailing_trivia(f.trivia.trailing_trivia(prop.range()), f.source),
+        format_trailing_trivia(f.comments.trailing(&prop), f.source),
     ]))
 }
 
+/// The `map` of a matrix transform is laid out with a line for every row of keys,
+/// and its `RC()` cells aligned in columns. With padding:
+/// ```dts
+/// map = <
+///     RC(0,0) RC(0,1) RC(0,2)    RC(0,3) RC(0,4) RC(0,5)
+///     RC(1,0) RC(1,1) RC(1,2)    RC(1,3) RC(1,4) RC(1,5)
+///             RC(2,1) RC(2,2)    RC(2,3) RC(2,4)
+/// >;
+/// ```
+/// Padding puts cells in the column of their `RC()`, unless the columns don't increase
+/// along the line. Without it, cells follow each other.
+fn format_matrix_transform_map(
+    prop: &NonBoolPropertyDefinition,
+    f: &FormatContext,
+) -> Option<Format> {
+    if !is_named(prop, "map")
+        || !parent_node(prop.syntax())
+            .is_some_and(|node| node.is_compatible("zmk,matrix-transform"))
+    {
+        return None;
+    }
+    let map = single_array(prop)?;
+    let lines = matrix_transform_lines(&map)?;
+    if lines.is_empty() {
+        return None;
+    }
+    let rows = lines
+        .into_iter()
+        .map(|line| {
+            let padded =
+                f.config.pad_matrix_transform && line.windows(2).all(|pair| pair[0].1 < pair[1].1);
+            line.into_iter()
+                .enumerate()
+                .map(|(index, (_, column, cell))| {
+                    let column = if padded { column } else { index };
+                    let mut grid_cell = GridCell::default();
+                    grid_cell.push(&cell, f);
+                    (column, grid_cell)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut widths = Vec::new();
+    for (column, cell) in rows.iter().flatten() {
+        if widths.len() <= *column {
+            widths.resize(column + 1, 0);
+        }
+        widths[*column] = widths[*column].max(cell.width);
+    }
+    Some(format_grid(&map, rows, &widths, f))
+}
+
+/// A single value follows the ` = `, while a list of values that doesn't fit on one line
+/// is broken to one value per line:
+/// ```dts
+/// bindings
+///     = <&macro_press &kp LSHFT>
+///     , <&macro_tap &kp A>
+/// ;
+/// ```
+/// Comments around a comma stay after the value before it.
 fn format_property_values(values: PropertyValues, f: &FormatContext) -> Format {
-    separated_list(
-        values
-            .into_iter()
-            .map(|value| format_property_value(value, f)),
-        tag(","),
-    )
+    let mut values = values
+        .into_iter()
+        .map(|value| {
+            let format = list([
+                format_property_value(value.clone(), f),
+                format_trailing_trivia(f.comments.trailing(&value), f.source),
+            ]);
+            (f.comments.leading(&value), format)
+        })
+        .collect::<Vec<_>>();
+    if values.len() == 1 {
+        let (leading, value) = values.remove(0);
+        return format_equal_value(leading, value, f);
+    }
+    let values = values
+        .into_iter()
+        .map(|(leading, value)| pair(format_leading_trivia(leading, f.source), value));
+    group([
+        indent([
+            text_break(1, TextBreakKind::Same),
+            tag("= "),
+            separated_list(values, pair(text_break(0, TextBreakKind::Same), tag(", "))),
+        ]),
+        text_break(0, TextBreakKind::Same),
+    ])
+}
+
+/// ` = ` followed by a single value. Comments before the value,
+/// after the `=` or on the lines before the value, indent the value under the property:
+/// ```dts
+/// display-name =
+///     // Shown on the display
+///     "Base";
+/// ```
+fn format_equal_value(before_value: Vec<Token>, value: Format, f: &FormatContext) -> Format {
+    if !before_value.iter().any(Token::is_comment) {
+        return list([space(), tag("="), space(), value]);
+    }
+    list([
+        space(),
+        tag("="),
+        indent([
+            // Comments on lines of their own start on the next line
+            if on_own_lines(&before_value) {
+                nil()
+            } else {
+                space()
+            },
+            format_leading_trivia(before_value, f.source),
+            value,
+        ]),
+    ])
 }
 
 fn format_property_value(value: PropertyValue, f: &FormatContext) -> Format {
@@ -55,18 +186,70 @@ fn format_property_value(value: PropertyValue, f: &FormatContext) -> Format {
     }
 }
 
+/// Bindings that don't fit on one line are broken one per line,
+/// a binding being a phandle followed by its parameters.
+/// Arrays of other cells are filled into as few lines as possible.
 fn format_array(array: ArrayValue, f: &FormatContext) -> Format {
-    list([
+    let mut bindings: Vec<Vec<Format>> = Vec::new();
+    let mut in_binding = false;
+    // A line comment after a cell already breaks the line before the next one
+    let mut line_ended = false;
+    for cell in array.cells() {
+        let is_phandle = matches!(cell, ArrayCell::Phandle(_));
+        let ends_line = f
+            .comments
+            .trailing(&cell)
+            .iter()
+            .any(Token::is_single_line_comment);
+        let cell = format_cell(cell, f);
+        match bindings.last_mut() {
+            Some(binding) if in_binding && !is_phandle => {
+                binding.extend([if line_ended { nil() } else { space() }, cell])
+            }
+            _ => bindings.push(vec![cell]),
+        }
+        in_binding |= is_phandle;
+        line_ended = ends_line;
+    }
+    let is_empty = bindings.is_empty();
+    // Bindings are broken one per line, other cells fill the lines
+    let bindings = bindings.into_iter().map(list);
+    let cells = if in_binding {
+        separated_list(bindings, text_break(1, TextBreakKind::Same))
+    } else {
+        fill(bindings, text_break(1, TextBreakKind::Same))
+    };
+    // Comments after `<` or in an empty array
+    let dangling = f.comments.dangling(&array);
+    // `< /* comment */ >` on one line
+    let space_before_close = if is_empty && dangling.iter().any(Token::is_comment) {
+        if_break(nil(), space())
+    } else {
+        nil()
+    };
+    group([
         tag("<"),
-        separated_list(array.into_iter().map(|cell| format_cell(cell, f)), space()),
+        indent([
+            format_trailing_trivia(dangling, f.source),
+            text_break(0, TextBreakKind::Same),
+            cells,
+        ]),
+        space_before_close,
+        text_break(0, TextBreakKind::Same),
         tag(">"),
     ])
 }
 
 fn format_cell(cell: ArrayCell, f: &FormatContext) -> Format {
-    match cell {
-        ArrayCell::Int(int_cell) => text(&int_cell, f.source),
-    }
+    list([
+        format_leading_trivia(f.comments.leading(&cell), f.source),
+        match &cell {
+            ArrayCell::Int(int_cell) => text(int_cell, f.source),
+            ArrayCell::Phandle(phandle) => text(phandle, f.source),
+            ArrayCell::Macro(macro_cell) => text(macro_cell, f.source),
+        },
+        format_trailing_trivia(f.comments.trailing(&cell), f.source),
+    ])
 }
 
 fn format_string(s: StringValue, f: &FormatContext) -> Format {
@@ -0,0 +1,128 @@
+//! Formatting a range of the source: only the smallest nodes and properties
+//! overlapping it are formatted, and the source around them is left as it is.
+//!
+//! The range is narrowed down from the statements of the document, going into
+//! the body of a node when the range is within its braces and overlaps some of its entries,
+//! unless a directive keeps the node as it's written.
+
+use crate::{
+    ast::{AstNode, Document, NodeBodyEntry, NodeDefinition, Statement},
+    formatter::{
+        comments::Comments,
+        ir::TextBreakKind,
+        rules::{indent, list, new_line, text_break},
+        FormatContext, FormatResult,
+    },
+    source::SourceRange,
+};
+
+use super::{
+    directives::{chunks, Chunk},
+    document::detect_layout,
+    node::format_entry,
+};
+
+/// Format of the entries overlapping `range`, with the range of the source it replaces.
+/// The format starts with a line break, to indent it to the depth of the entries.
+pub(crate) fn format_range(
+    document: Document,
+    range: SourceRange,
+    f: &mut FormatContext,
+) -> Option<(SourceRange, FormatResult)> {
+    detect_layout(&document, f);
+    f.comments = Comments::new(&document);
+    let mut entries = document
+        .statements()
+        .into_iter()
+        .map(|statement| match statement {
+            Statement::Node(node) => NodeBodyEntry::Node(node),
+        })
+        .collect::<Vec<_>>();
+    let mut depth = 0;
+    loop {
+        let overlapping = entries
+            .iter()
+            .filter(|entry| overlaps(entry.range(), range))
+            .collect::<Vec<_>>();
+        let (first, last) = (overlapping.first()?, overlapping.last()?);
+        let run = SourceRange::new(first.range().start(), last.range().end());
+        // Whole runs of entries kept by directives, with everything inside them
+        let chunks = chunks(entries, f)
+            .into_iter()
+            .filter(|chunk| overlaps(chunk_range(chunk), run))
+            .collect::<Vec<_>>();
+        if let [Chunk::Format(NodeBodyEntry::Node(node))] = &chunks[..] {
+            if let Some(children) = children_around(node, range) {
+                entries = children;
+                depth += 1;
+                continue;
+            }
+        }
+
+        // Nothing to format in entries kept as they're written
+        if chunks
+            .iter()
+            .all(|chunk| matches!(chunk, Chunk::Verbatim(..)))
+        {
+            return None;
+        }
+        let replaced = SourceRange::new(
+            chunk_range(chunks.first()?).start(),
+            chunk_range(chunks.last()?).end(),
+        );
+        f.comments.range = Some(replaced);
+        let format = format_chunks(chunks, depth, f);
+        return Some((replaced, format));
+    }
+}
+
+fn format_chunks(
+    chunks: Vec<Chunk<NodeBodyEntry>>,
+    depth: usize,
+    f: &mut FormatContext,
+) -> FormatResult {
+    let mut formatted = Vec::new();
+    for chunk in chunks {
+        // Statements are only separated by their trivia
+        if !formatted.is_empty() && depth > 0 {
+            formatted.push(text_break(0, TextBreakKind::NewLine));
+        }
+        formatted.push(format_entry(chunk, f)?);
+    }
+    let mut format = list([new_line(), list(formatted)]);
+    for _ in 0..depth {
+        format = indent([format]);
+    }
+    Ok(format)
+}
+
+/// Entries of the body of `node`, when `range` is within its braces and overlaps some of them
+fn children_around(node: &NodeDefinition, range: SourceRange) -> Option<Vec<NodeBodyEntry>> {
+    let body = node.body().ok()?;
+    let within_braces = body.l_curly().ok()?.range().end() <= range.start()
+        && range.end() <= body.r_curly().ok()?.range().start();
+    let children = body
+        .entries()
+        .ok()?
+        .entries()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let overlapping = children.iter().any(|child| overlaps(child.range(), range));
+    (within_braces && overlapping).then_some(children)
+}
+
+/// Whether `range` overlaps `entry`, or touches it if it's empty
+fn overlaps(entry: SourceRange, range: SourceRange) -> bool {
+    if range.start() == range.end() {
+        entry.start() <= range.start() && range.start() <= entry.end()
+    } else {
+        entry.start() < range.end() && range.start() < entry.end()
+    }
+}
+
+fn chunk_range(chunk: &Chunk<NodeBodyEntry>) -> SourceRange {
+    match chunk {
+        Chunk::Format(entry) => entry.range(),
+        Chunk::Verbatim(first, last) => SourceRange::new(first.range().start(), last.range().end()),
+    }
+}
@@ -1,4 +1,4 @@
-use std::{collections::LinkedList, fmt::Debug};
+use std::{cell::Cell, collections::LinkedList, fmt::Debug};
 
 /// Text Verbatim
 #[derive(Clone)]
@@ -35,15 +35,47 @@ pub(crate) struct Concat(pub LinkedList<Format>);
 #[derive(Clone)]
 pub(crate) struct Group {
     pub(super) formats: LinkedList<Format>,
-    pub(super) broken_to_multilines: Option<bool>,
+    /// Whether the group contains a hard line break, computed lazily by the writer
+    pub(super) broken_to_multilines: Cell<Option<bool>>,
 }
 
+/// Indent the new lines of all children by one more level
+#[derive(Clone)]
+pub(crate) struct Indent(pub LinkedList<Format>);
+
+/// Indent the new lines of all children to the column they start at
+#[derive(Clone)]
+pub(crate) struct Align(pub LinkedList<Format>);
+
+/// Choose the content depending on whether the enclosing group
+/// is broken to multiple lines
+#[derive(Clone)]
+pub(crate) struct IfBreak {
+    pub(super) broken: Box<Format>,
+    pub(super) flat: Box<Format>,
+}
+
+/// Items alternating with separators, put as many items on a line as fit,
+/// and only break the separators before the items that don't
+#[derive(Clone)]
+pub(crate) struct Fill(pub Vec<Format>);
+
+/// Deferred until right before the next new line, like a trailing comment.
+/// Its width is not counted when fitting groups
+#[derive(Clone)]
+pub(crate) struct LineSuffix(pub LinkedList<Format>);
+
 #[derive(Clone)]
 pub(crate) enum Format {
     Text(Text),
     TextBreak(TextBreak),
     Concat(Concat),
     Group(Group),
+    Indent(Indent),
+    Align(Align),
+    IfBreak(IfBreak),
+    Fill(Fill),
+    LineSuffix(LineSuffix),
     Nil,
 }
 
@@ -73,6 +105,43 @@ impl Debug for Group {
     }
 }
 
+impl Debug for Indent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Indent ")?;
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
+
+impl Debug for Align {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Align ")?;
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
+
+impl Debug for IfBreak {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IfBreak")
+            .field("broken", &self.broken)
+            .field("flat", &self.flat)
+            .finish()
+    }
+}
+
+impl Debug for Fill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Fill ")?;
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
+
+impl Debug for LineSuffix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LineSuffix ")?;
+        f.debug_list().entries(self.0.iter()).finish()
+    }
+}
+
 impl Debug for Format {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -80,6 +149,11 @@ impl Debug for Format {
             Self::TextBreak(arg0) => write!(f, "{:#?}", arg0),
             Self::Concat(arg0) => write!(f, "{:#?}", arg0),
             Self::Group(arg0) => write!(f, "{:#?}", arg0),
+            Self::Indent(arg0) => write!(f, "{:#?}", arg0),
+            Self::Align(arg0) => write!(f, "{:#?}", arg0),
+            Self::IfBreak(arg0) => write!(f, "{:#?}", arg0),
+            Self::Fill(arg0) => write!(f, "{:#?}", arg0),
+            Self::LineSuffix(arg0) => write!(f, "{:#?}", arg0),
             Self::Nil => write!(f, "Nil"),
         }
     }
@@ -110,10 +184,43 @@ pub(super) fn concat(formats: impl IntoIterator<Item = Format>) -> Format {
 pub(super) fn group(formats: impl IntoIterator<Item = Format>) -> Format {
     Format::Group(Group {
         formats: expand_concatenated_format(formats),
-        broken_to_multilines: None,
+        broken_to_multilines: Cell::new(None),
     })
 }
 
+/// Indent the new lines of the formatted text by one more level
+pub(super) fn indent(formats: impl IntoIterator<Item = Format>) -> Format {
+    Format::Indent(Indent(expand_concatenated_format(formats)))
+}
+
+/// Indent the new lines of the formatted text to the column it starts at
+pub(super) fn align(formats: impl IntoIterator<Item = Format>) -> Format {
+    Format::Align(Align(expand_concatenated_format(formats)))
+}
+
+/// Write `broken` if the enclosing group is broken to multiple lines,
+/// `flat` otherwise
+pub(super) fn if_break(broken: Format, flat: Format) -> Format {
+    Format::IfBreak(IfBreak {
+        broken: Box::new(broken),
+        flat: Box::new(flat),
+    })
+}
+
+/// Fill lines with as many items as fit, separated by `separator`,
+/// which should be a text break
+pub(super) fn fill(items: impl IntoIterator<Item = Format>, separator: Format) -> Format {
+    let items = items
+        .into_iter()
+        .filter(|item| !matches!(item, Format::Nil));
+    Format::Fill(Fill(itertools::intersperse(items, separator).collect()))
+}
+
+/// Defer the formatted text until right before the next new line
+pub(super) fn line_suffix(formats: impl IntoIterator<Item = Format>) -> Format {
+    Format::LineSuffix(LineSuffix(expand_concatenated_format(formats)))
+}
+
 fn expand_concatenated_format(formats: impl IntoIterator<Item = Format>) -> LinkedList<Format> {
     let mut combined = LinkedList::new();
     for format in formats {
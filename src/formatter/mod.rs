@@ -1,3 +1,5 @@
+mod check;
+mod comments;
 mod config;
 #[allow(clippy::module_inception)]
 mod context;
@@ -6,12 +8,20 @@ mod ir;
 pub(crate) mod rules;
 mod writer;
 
-use crate::{ast::Document, source::Source, token_source::TokenSource};
-pub(crate) use config::Config;
+use crate::{
+    ast::{Document, MissingSyntax},
+    parser::parse,
+    source::{Source, SourceIndex, SourceRange},
+};
+#[cfg(test)]
+pub(crate) use check::{divergence, instability, missing_comment};
+pub(crate) use check::{Divergence, Instability};
+pub use config::{Config, EndOfLine, IndentStyle};
 #[cfg(test)]
 pub(crate) use formatters::format_document;
 #[cfg(not(test))]
 use formatters::format_document;
+use formatters::format_range as format_range_entries;
 
 pub(crate) use context::FormatContext;
 pub(crate) use ir::Format;
@@ -19,17 +29,125 @@ pub(crate) use ir::Format;
 pub(crate) use ir::TextBreakKind;
 pub(crate) use writer::Writer;
 
-type FormatResult = Result<Format, ()>;
+type FormatResult = Result<Format, MissingSyntax>;
+
+/// Formatting that would lose part of the document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FormatError {
+    /// The document has syntax errors, its nodes can't all be formatted
+    MissingSyntax,
+    /// The comment at this range of the source is missing from the formatted text
+    MissingComment(SourceRange),
+    /// The formatted text isn't equivalent to the source, found by [`Config::verify`]
+    Diverged(Divergence),
+    /// The formatted text changes when formatted again, found by [`Config::check_idempotency`]
+    Unstable(Instability),
+}
 
-pub(crate) fn format(doc: Document, source: &Source, token_source: TokenSource) -> String {
-    let mut format_context = FormatContext::new(source, &token_source);
+pub(crate) fn format(
+    doc: Document,
+    source: &Source,
+    config: &Config,
+) -> Result<String, FormatError> {
+    let mut format_context = FormatContext::new(source, config);
     let format = format_document(doc, &mut format_context);
     let Ok(format) = format else {
-        // FIXME: don't nuke user's file with syntax errors
-        return "".to_owned();
+        return Err(FormatError::MissingSyntax);
+    };
+
+    let config = Config {
+        end_of_line: config.end_of_line.detect(source.as_str()),
+        ..config.clone()
+    };
+    let mut writer = Writer::new(config.clone());
+    let formatted = check(source, writer.write(format), &config)?;
+    if config.check_idempotency {
+        let formatted_source = Source::new(&formatted);
+        let (formatted_doc, _) = parse(&formatted_source);
+        let config = Config {
+            check_idempotency: false,
+            ..config
+        };
+        let reformatted = self::format(formatted_doc, &formatted_source, &config)?;
+        if let Some(instability) = check::instability(&formatted, &reformatted) {
+            return Err(FormatError::Unstable(instability));
+        }
+    }
+    Ok(formatted)
+}
+
+/// Format the nodes and properties overlapping `range`, leaving the rest of the source as it is
+pub(crate) fn format_range(
+    doc: Document,
+    source: &Source,
+    range: SourceRange,
+    config: &Config,
+) -> Result<String, FormatError> {
+    let text = source.as_str();
+    let mut format_context = FormatContext::new(source, config);
+    let Some((replaced, Ok(format))) = format_range_entries(doc, range, &mut format_context) else {
+        return Ok(text.to_owned());
+    };
+
+    let end_of_line = config.end_of_line.detect(text);
+    let config = Config {
+        end_of_line,
+        insert_final_newline: false,
+        ..config.clone()
+    };
+    let mut writer = Writer::new(config.clone());
+    let formatted = writer.write(format);
+    // The format starts on a new line, indented like the entries it replaces
+    let formatted = formatted
+        .strip_prefix(end_of_line.as_str())
+        .unwrap_or(&formatted);
+    let mut range = core::ops::Range::from(replaced);
+    // The indentation before the entries is replaced, unless code precedes them on their line
+    let line_start = text[..range.start].rfind('\n').map_or(0, |index| index + 1);
+    let formatted = if text[line_start..range.start]
+        .trim_matches([' ', '\t'])
+        .is_empty()
+    {
+        range.start = line_start;
+        formatted
+    } else {
+        formatted.trim_start_matches([' ', '\t'])
     };
+    let result = check(
+        source,
+        [&text[..range.start], formatted, &text[range.end..]].concat(),
+        &config,
+    )?;
+    if config.check_idempotency {
+        // Format the range of the formatted entries again
+        let formatted_range = SourceRange::new(
+            SourceIndex::new(range.start),
+            SourceIndex::new(range.start + formatted.len()),
+        );
+        let result_source = Source::new(&result);
+        let (result_doc, _) = parse(&result_source);
+        let config = Config {
+            check_idempotency: false,
+            ..config
+        };
+        let reformatted = self::format_range(result_doc, &result_source, formatted_range, &config)?;
+        if let Some(instability) = check::instability(&result, &reformatted) {
+            return Err(FormatError::Unstable(instability));
+        }
+    }
+    Ok(result)
+}
 
-    let config = Config::default();
-    let mut writer = Writer::new(config);
-    writer.write(format)
+/// Refuse the formatted text if it lost a comment of the source,
+/// or if it isn't equivalent to the source when verifying
+fn check(source: &Source, formatted: String, config: &Config) -> Result<String, FormatError> {
+    if let Some(range) = check::missing_comment(source, &formatted) {
+        return Err(FormatError::MissingComment(range));
+    }
+    if config.verify {
+        if let Some(divergence) = check::divergence(source, &formatted) {
+            return Err(FormatError::Diverged(divergence));
+        }
+    }
+    Ok(formatted)
 }
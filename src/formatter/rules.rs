@@ -1,6 +1,9 @@
 use itertools::Itertools;
 
-use crate::{ast::AstNode, lexer::Token, source::SourceRange};
+use crate::{
+    ast::AstNode,
+    lexer::{Token, TokenKind},
+};
 
 use super::{
     ir::{self, TextBreakKind},
@@ -9,9 +12,7 @@ use super::{
 
 /// Write node's content as it is.
 pub(crate) fn text<T: AstNode>(node: &T, source: &Source) -> Format {
-    let node_text =
-        std::str::from_utf8(&source[node.range()]).expect("Node must be a valid utf8 string");
-    ir::text(node_text)
+    ir::text(source.text(node.range()))
 }
 
 /// A normal text.
@@ -56,16 +57,57 @@ pub(crate) fn group(formats: impl IntoIterator<Item = Format>) -> Format {
     ir::group(formats)
 }
 
+/// Indent the new lines of formatted text by one more level
+pub(crate) fn indent(formats: impl IntoIterator<Item = Format>) -> Format {
+    ir::indent(formats)
+}
+
+/// Indent the new lines of formatted text to the column it starts at
+pub(crate) fn align(formats: impl IntoIterator<Item = Format>) -> Format {
+    ir::align(formats)
+}
+
+/// Write `broken` if the enclosing group is broken to multiple lines, `flat` otherwise
+pub(crate) fn if_break(broken: Format, flat: Format) -> Format {
+    ir::if_break(broken, flat)
+}
+
+/// Fill lines with as many formatted items as fit,
+/// the separator should be a text break
+pub(crate) fn fill(items: impl IntoIterator<Item = Format>, separator: Format) -> Format {
+    ir::fill(items, separator)
+}
+
+/// Write formatted text at the end of the line, like a trailing comment
+pub(crate) fn line_suffix(formats: impl IntoIterator<Item = Format>) -> Format {
+    ir::line_suffix(formats)
+}
+
 /// Do nothing
 pub(crate) fn nil() -> Format {
     ir::nil()
 }
 
+/// Comments before a token are on their own lines, unless they start the document
 pub(crate) fn format_leading_trivia(trivia: Vec<Token>, source: &Source) -> Format {
-    group(trivia.into_iter().map(|token| {
-        let comment_text = text_from_range(token.range, source);
+    // Line breaks are only kept around comments, or they would break lines that fit
+    if !trivia.iter().any(Token::is_comment) {
+        return nil();
+    }
+    let own_lines = on_own_lines(&trivia);
+    // Whether a comment follows on the same line
+    let comment_follows = |index: usize| {
+        trivia[index + 1..]
+            .iter()
+            .find(|token| token.kind != TokenKind::SPACE)
+            .is_some_and(Token::is_comment)
+    };
+    let trivia = group(trivia.iter().enumerate().map(|(index, token)| {
+        let comment_text = source.text(token.range);
         if token.is_single_line_comment() {
             format_single_line_comment(comment_text)
+        } else if token.is_block_comment() && comment_follows(index) {
+            pair(format_block_comment(comment_text), space())
         } else if token.is_block_comment() {
             let format = format_block_comment(comment_text);
             pair(format, text_break(1, TextBreakKind::Same))
@@ -74,30 +116,64 @@ pub(crate) fn format_leading_trivia(trivia: Vec<Token>, source: &Source) -> Form
         } else {
             nil()
         }
-    }))
+    }));
+    if own_lines {
+        pair(new_line(), trivia)
+    } else {
+        trivia
+    }
 }
 
-pub(crate) fn format_trailing_trivia(trivia: Vec<Token>, source: &Source) -> Format {
-    group(trivia.into_iter().map(|token| {
-        let comment_text = text_from_range(token.range, source);
+/// Comments on the line of a token stay after it, unless one of them spans lines.
+/// The trivia after the end of the line is formatted like leading trivia.
+pub(crate) fn format_trailing_trivia(mut trivia: Vec<Token>, source: &Source) -> Format {
+    let line_end = trivia
+        .iter()
+        .position(|token| token.is_newline() || token.is_single_line_comment())
+        .map_or(trivia.len(), |index| {
+            index + usize::from(trivia[index].is_single_line_comment())
+        });
+    let next_lines = trivia.split_off(line_end);
+    // Only a comment spanning lines breaks the line, a long one doesn't
+    let spans_lines = trivia
+        .iter()
+        .any(|token| token.is_block_comment() && source.text(token.range).contains('\n'));
+    let mut line_comment = None;
+    let line = list(trivia.into_iter().map(|token| {
+        let comment_text = source.text(token.range);
         if token.is_single_line_comment() {
-            pair(space(), format_single_line_comment(comment_text))
+            // Don't let the comment push the code before it to break
+            line_comment = Some(line_suffix([space(), ir::text(comment_text)]));
+            nil()
+        } else if token.is_block_comment() && spans_lines {
+            pair(new_line(), format_block_comment(comment_text))
         } else if token.is_block_comment() {
-            let format = format_block_comment(comment_text);
-            pair(text_break(1, TextBreakKind::Same), format)
-        } else if token.is_newline() {
-            ir::text_break(0, ir::TextBreakKind::Discretion)
+            pair(space(), format_block_comment(comment_text))
         } else {
             nil()
         }
-    }))
+    }));
+    list([
+        line,
+        // Ends the line, without breaking the block comments before it
+        line_comment.map_or_else(nil, |comment| pair(comment, new_line())),
+        format_leading_trivia(next_lines, source),
+    ])
+}
+
+/// Whether the first comment of `trivia` starts a line
+pub(crate) fn on_own_lines(trivia: &[Token]) -> bool {
+    trivia
+        .iter()
+        .take_while(|token| !token.is_comment())
+        .any(Token::is_newline)
 }
 
 fn format_single_line_comment(comment: &str) -> Format {
     pair(ir::text(comment), new_line())
 }
 
-fn format_block_comment(comment: &str) -> Format {
+pub(crate) fn format_block_comment(comment: &str) -> Format {
     let comment_lines = comment.lines().collect_vec();
     let mut formatted_comment_lines = Vec::new();
     for line in comment_lines {
@@ -116,7 +192,3 @@ fn format_block_comment(comment: &str) -> Format {
     }
     list(formatted_comment_lines)
 }
-
-fn text_from_range<'src>(range: SourceRange, source: &'src Source) -> &'src str {
-    std::str::from_utf8(&source[range]).expect("Node must be a valid utf8 string")
-}
@@ -1,6 +1,8 @@
 use super::{
-    config::Config,
-    ir::{Concat, Format, Group, TextBreak, TextBreakKind},
+    config::{Config, IndentStyle},
+    ir::{
+        Align, Concat, Fill, Format, Group, IfBreak, Indent, LineSuffix, TextBreak, TextBreakKind,
+    },
 };
 
 #[cfg(test)]
@@ -10,6 +12,49 @@ use std::cell::Cell;
 pub(crate) struct Writer {
     buffer: String,
     new_line_tracker: NewLineTracker,
+    /// Width of the text already written on the current line
+    line_width: u32,
+    /// Line suffixes waiting for the next new line
+    line_suffixes: Vec<Format>,
+}
+
+/// Whether the breaks of a group are written as new lines
+#[derive(Clone, Copy)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+/// Indentation added by `Indent` and `Align`, on top of the one
+/// from `TextBreakKind::Open` and `TextBreakKind::Close`
+#[derive(Clone, Copy, Default)]
+struct Indentation {
+    levels: u32,
+    spaces: u32,
+}
+
+#[derive(Clone, Copy)]
+enum Content<'a> {
+    Format(&'a Format),
+    /// The remaining items of a `Fill`, starting with an item
+    Fill(&'a [Format]),
+}
+
+#[derive(Clone, Copy)]
+struct Command<'a> {
+    indentation: Indentation,
+    mode: Mode,
+    content: Content<'a>,
+}
+
+impl<'a> Command<'a> {
+    fn new(indentation: Indentation, mode: Mode, format: &'a Format) -> Self {
+        Self {
+            indentation,
+            mode,
+            content: Content::Format(format),
+        }
+    }
 }
 
 impl Writer {
@@ -17,82 +62,326 @@ impl Writer {
         Writer {
             buffer: String::new(),
             new_line_tracker: NewLineTracker::new(config),
+            line_width: 0,
+            line_suffixes: Vec::new(),
         }
     }
 
     pub(crate) fn write(&mut self, node: Format) -> String {
-        self.write_with_context(node, WriteContext::Concat);
+        // Pending commands, the next one to write is at the end
+        let mut commands = vec![Command::new(Indentation::default(), Mode::Break, &node)];
+        while let Some(command) = commands.pop() {
+            let Command {
+                indentation,
+                mode,
+                content,
+            } = command;
+            let format = match content {
+                Content::Format(format) => format,
+                Content::Fill(items) => {
+                    self.write_fill(items, command, &mut commands);
+                    continue;
+                }
+            };
+            match format {
+                Format::Text(text) => self.write_text(&text.0, indentation),
+                Format::TextBreak(text_break) => self.write_text_break(text_break, mode),
+                Format::Concat(Concat(formats)) => {
+                    push_commands(&mut commands, formats, indentation, mode)
+                }
+                Format::Group(group) => {
+                    let mode = match mode {
+                        Mode::Break
+                            if should_break(group)
+                                || !self.fits_group(group, &commands, indentation) =>
+                        {
+                            Mode::Break
+                        }
+                        _ => Mode::Flat,
+                    };
+                    push_commands(&mut commands, &group.formats, indentation, mode)
+                }
+                Format::Indent(Indent(formats)) => {
+                    let indentation = Indentation {
+                        levels: indentation.levels + 1,
+                        ..indentation
+                    };
+                    push_commands(&mut commands, formats, indentation, mode)
+                }
+                Format::Align(Align(formats)) => {
+                    let column = self.column(indentation);
+                    let indentation = Indentation {
+                        spaces: indentation.spaces
+                            + column
+                                .saturating_sub(self.new_line_tracker.indent_width(indentation)),
+                        ..indentation
+                    };
+                    push_commands(&mut commands, formats, indentation, mode)
+                }
+                Format::IfBreak(IfBreak { broken, flat }) => {
+                    let format = match mode {
+                        Mode::Break => broken,
+                        Mode::Flat => flat,
+                    };
+                    commands.push(Command::new(indentation, mode, format))
+                }
+                Format::Fill(Fill(items)) => commands.push(Command {
+                    content: Content::Fill(items),
+                    ..command
+                }),
+                Format::LineSuffix(LineSuffix(formats)) => {
+                    self.line_suffixes.extend(formats.iter().cloned())
+                }
+                Format::Nil => {}
+            }
+        }
+        self.write_line_suffixes();
+        self.trim_line_end();
+        if self.new_line_tracker.config.insert_final_newline {
+            let end = self.buffer.trim_end_matches(['\r', '\n']).len();
+            self.buffer.truncate(end);
+            self.buffer
+                .push_str(self.new_line_tracker.config.end_of_line.as_str());
+        }
         self.buffer.clone()
     }
 
-    fn write_with_context(&mut self, node: Format, context: WriteContext) {
-        match node {
-            Format::Text(text) => self.write_text(&text.0),
-            Format::TextBreak(text_break) => self.write_text_break(text_break, context),
-            Format::Concat(concat) => self.write_concat(concat),
-            Format::Group(group) => self.write_group(group),
+    fn write_text(&mut self, text: &str, indentation: Indentation) {
+        if self.new_line_tracker.has_pending_new_lines() {
+            self.write_line_suffixes();
+            self.trim_line_end();
+        }
+        let new_lines = self.new_line_tracker.flush(indentation);
+        self.push_str(&new_lines);
+        // Texts spanning lines, like block comments or entries kept as written
+        if text.contains('\n') {
+            let end_of_line = self.new_line_tracker.config.end_of_line.as_str();
+            self.push_str(&text.replace("\r\n", "\n").replace('\n', end_of_line));
+        } else {
+            self.push_str(text);
+        }
+    }
+
+    fn write_text_break(&mut self, text_break: &TextBreak, mode: Mode) {
+        match mode {
+            Mode::Break => match text_break.kind {
+                TextBreakKind::Open => self.new_line_tracker.indent(),
+                TextBreakKind::Close => self.new_line_tracker.dedent(),
+                TextBreakKind::Discretion => self.new_line_tracker.buffer_discretion(),
+                TextBreakKind::NewLine => self.new_line_tracker.buffer_new_line(),
+                TextBreakKind::Same => self.new_line_tracker.buffer_new_line(),
+            },
+            // Spaces before a line break would be trimmed anyway
+            Mode::Flat if self.new_line_tracker.has_pending_new_lines() => {}
+            Mode::Flat => self.push_str(&" ".repeat(text_break.size as usize)),
+        }
+    }
+
+    /// Write the first item of the fill, and decide whether the separator after it breaks,
+    /// by checking if the next item also fits on the line
+    fn write_fill<'a>(
+        &self,
+        items: &'a [Format],
+        command: Command<'a>,
+        commands: &mut Vec<Command<'a>>,
+    ) {
+        let Command {
+            indentation, mode, ..
+        } = command;
+        let Some((item, rest)) = items.split_first() else {
+            return;
+        };
+        if let Mode::Flat = mode {
+            push_commands(commands, items, indentation, mode);
+            return;
+        }
+        // An item with a hard line break, like a line comment, is written broken
+        let hard_break = analyze(item);
+        let item_mode = if hard_break {
+            Mode::Break
+        } else {
+            self.fits_mode(&[Command::new(indentation, Mode::Flat, item)], indentation)
+        };
+        let Some((separator, rest)) = rest.split_first() else {
+            commands.push(Command::new(indentation, item_mode, item));
+            return;
+        };
+        let separator_mode = match rest.first() {
+            Some(next_item) => self.fits_mode(
+                &[
+                    Command::new(indentation, Mode::Flat, next_item),
+                    Command::new(indentation, Mode::Flat, separator),
+                    Command::new(indentation, Mode::Flat, item),
+                ],
+                indentation,
+            ),
+            None => item_mode,
+        };
+        commands.push(Command {
+            content: Content::Fill(rest),
+            ..command
+        });
+        commands.push(Command::new(indentation, separator_mode, separator));
+        commands.push(Command::new(indentation, item_mode, item));
+    }
+
+    fn write_line_suffixes(&mut self) {
+        for format in std::mem::take(&mut self.line_suffixes) {
+            self.write_flat(&format);
+        }
+    }
+
+    /// Write line suffixes on the current line, no matter the breaks in them
+    fn write_flat(&mut self, format: &Format) {
+        let mut write_all = |formats: &mut dyn Iterator<Item = &Format>| {
+            formats.for_each(|format| self.write_flat(format))
+        };
+        match format {
+            Format::Text(text) => self.push_str(&text.0),
+            Format::TextBreak(text_break) => self.push_str(&" ".repeat(text_break.size as usize)),
+            Format::Concat(Concat(formats))
+            | Format::Group(Group { formats, .. })
+            | Format::Indent(Indent(formats))
+            | Format::Align(Align(formats))
+            | Format::LineSuffix(LineSuffix(formats)) => write_all(&mut formats.iter()),
+            Format::Fill(Fill(items)) => write_all(&mut items.iter()),
+            Format::IfBreak(IfBreak { flat, .. }) => self.write_flat(flat),
             Format::Nil => {}
         }
     }
 
-    fn write_text(&mut self, text: &str) {
-        self.buffer.push_str(&self.new_line_tracker.flush());
+    /// Drop the spaces of breaks written at the end of the line
+    fn trim_line_end(&mut self) {
+        let end = self.buffer.trim_end_matches([' ', '\t']).len();
+        self.buffer.truncate(end);
+    }
+
+    fn push_str(&mut self, text: &str) {
+        let tab_width = self.new_line_tracker.config.indent_width;
+        match text.rfind('\n') {
+            Some(new_line) => self.line_width = width(&text[new_line + 1..], tab_width),
+            None => self.line_width += width(text, tab_width),
+        }
         self.buffer.push_str(text);
     }
 
-    fn write_text_break(&mut self, text_break: TextBreak, context: WriteContext) {
-        match context {
-            WriteContext::Group { break_to_lines } => {
-                if break_to_lines {
-                    match text_break.kind {
-                        TextBreakKind::Open => self.new_line_tracker.indent(),
-                        TextBreakKind::Close => self.new_line_tracker.dedent(),
-                        TextBreakKind::Discretion => self.new_line_tracker.buffer_discretion(),
-                        TextBreakKind::NewLine => self.new_line_tracker.buffer_new_line(),
-                        TextBreakKind::Same => self.new_line_tracker.buffer_new_line(),
-                    }
-                } else {
-                    for _ in 0..text_break.size {
-                        self.buffer.push(' ');
-                    }
-                }
-            }
-            WriteContext::Concat => self.new_line_tracker.buffer_new_line(),
+    /// Column the next text will be written at
+    fn column(&self, indentation: Indentation) -> u32 {
+        if self.new_line_tracker.has_pending_new_lines() {
+            self.new_line_tracker.indent_width(indentation)
+        } else {
+            self.line_width
         }
     }
 
-    fn write_concat(&mut self, Concat(formats): Concat) {
-        formats
-            .into_iter()
-            .for_each(|format| self.write_with_context(format, WriteContext::Concat));
+    fn fits_group<'a>(
+        &self,
+        group: &'a Group,
+        rest: &[Command<'a>],
+        indentation: Indentation,
+    ) -> bool {
+        let mut next = Vec::new();
+        push_commands(&mut next, &group.formats, indentation, Mode::Flat);
+        self.fits(next, rest, indentation)
     }
 
-    fn write_group(
-        &mut self,
-        Group {
-            mut formats,
-            broken_to_multilines,
-        }: Group,
-    ) {
-        let should_break = match broken_to_multilines {
-            Some(should_break) => should_break,
-            None => formats.iter_mut().any(analyze),
-        };
+    fn fits_mode(&self, next: &[Command], indentation: Indentation) -> Mode {
+        if self.fits(next.to_vec(), &[], indentation) {
+            Mode::Flat
+        } else {
+            Mode::Break
+        }
+    }
 
-        for format in formats {
-            self.write_with_context(
-                format,
-                WriteContext::Group {
-                    break_to_lines: should_break,
+    /// Whether the `next` commands fit on the rest of the current line,
+    /// together with the `rest` of the commands up to the next line break.
+    /// A hard line break in the `next` commands starts a new line to measure.
+    fn fits<'a>(
+        &self,
+        mut next: Vec<Command<'a>>,
+        rest: &[Command<'a>],
+        indentation: Indentation,
+    ) -> bool {
+        let mut remaining_width =
+            i64::from(self.new_line_tracker.config.max_width) - i64::from(self.column(indentation));
+        let mut rest = rest.iter().rev();
+        while remaining_width >= 0 {
+            let in_next = !next.is_empty();
+            let Some(Command {
+                indentation,
+                mode,
+                content,
+            }) = next.pop().or_else(|| rest.next().copied())
+            else {
+                return true;
+            };
+            let format = match content {
+                Content::Format(format) => format,
+                Content::Fill(items) => {
+                    push_commands(&mut next, items, indentation, mode);
+                    continue;
+                }
+            };
+            match format {
+                Format::Text(text) => {
+                    let tab_width = self.new_line_tracker.config.indent_width;
+                    remaining_width -= i64::from(width(&text.0, tab_width))
+                }
+                Format::TextBreak(text_break) => match (mode, &text_break.kind) {
+                    (Mode::Flat, TextBreakKind::NewLine) if in_next => {
+                        remaining_width = i64::from(self.new_line_tracker.config.max_width)
+                            - i64::from(self.new_line_tracker.indent_width(indentation))
+                    }
+                    (Mode::Flat, _) => remaining_width -= i64::from(text_break.size),
+                    (Mode::Break, _) => return true,
                 },
-            )
+                Format::Concat(Concat(formats))
+                | Format::Indent(Indent(formats))
+                | Format::Align(Align(formats)) => {
+                    push_commands(&mut next, formats, indentation, mode)
+                }
+                Format::Group(group) => {
+                    let mode = if should_break(group) {
+                        Mode::Break
+                    } else {
+                        mode
+                    };
+                    push_commands(&mut next, &group.formats, indentation, mode)
+                }
+                Format::IfBreak(IfBreak { broken, flat }) => {
+                    let format = match mode {
+                        Mode::Break => broken,
+                        Mode::Flat => flat,
+                    };
+                    next.push(Command::new(indentation, mode, format))
+                }
+                Format::Fill(Fill(items)) => push_commands(&mut next, items, indentation, mode),
+                Format::LineSuffix(_) | Format::Nil => {}
+            }
         }
+        false
     }
 }
 
-enum WriteContext {
-    Concat,
-    Group { break_to_lines: bool },
+fn push_commands<'a>(
+    commands: &mut Vec<Command<'a>>,
+    formats: impl IntoIterator<Item = &'a Format, IntoIter: DoubleEndedIterator>,
+    indentation: Indentation,
+    mode: Mode,
+) {
+    commands.extend(
+        formats
+            .into_iter()
+            .rev()
+            .map(|format| Command::new(indentation, mode, format)),
+    );
+}
+
+/// Columns taken by `text`, with tabs as wide as an indentation level
+fn width(text: &str, tab_width: u32) -> u32 {
+    text.chars()
+        .map(|char| if char == '\t' { tab_width } else { 1 })
+        .sum()
 }
 
 struct NewLineTracker {
@@ -112,14 +401,21 @@ impl NewLineTracker {
         }
     }
 
-    fn flush(&mut self) -> String {
+    fn flush(&mut self, indentation: Indentation) -> String {
         let mut output = String::new();
         for _ in 0..self.num_bufferred_new_lines {
-            output.push('\n');
+            output.push_str(self.config.end_of_line.as_str());
         }
         if self.num_bufferred_new_lines > 0 {
-            for _ in 0..(self.current_indent_level * self.config.indent_width) {
-                output.push(' ')
+            match self.config.indent_style {
+                IndentStyle::Space => {
+                    output.push_str(&" ".repeat(self.indent_width(indentation) as usize))
+                }
+                IndentStyle::Tab => {
+                    let levels = self.current_indent_level + indentation.levels;
+                    output.push_str(&"\t".repeat(levels as usize));
+                    output.push_str(&" ".repeat(indentation.spaces as usize));
+                }
             }
         }
         self.bufferred_discretionary_new_line = false;
@@ -127,6 +423,15 @@ impl NewLineTracker {
         output
     }
 
+    fn has_pending_new_lines(&self) -> bool {
+        self.num_bufferred_new_lines > 0
+    }
+
+    fn indent_width(&self, indentation: Indentation) -> u32 {
+        (self.current_indent_level + indentation.levels) * self.config.indent_width
+            + indentation.spaces
+    }
+
     fn indent(&mut self) {
         self.current_indent_level += 1;
         self.buffer_new_line();
@@ -164,33 +469,264 @@ thread_local! {
     static COUNTER: Cell<usize> = const { Cell::new(0) };
 }
 
-fn analyze(format: &mut Format) -> bool {
+fn analyze(format: &Format) -> bool {
     #[cfg(test)]
     COUNTER.with(|c| c.set(c.get() + 1));
 
     match format {
         Format::TextBreak(text_break) => matches!(text_break.kind, TextBreakKind::NewLine),
-        Format::Concat(subnodes) => subnodes.0.iter_mut().any(analyze),
-        Format::Group(group) => {
-            let should_break = match group.broken_to_multilines {
-                Some(should_break) => should_break,
-                None => group.formats.iter_mut().any(analyze),
-            };
-            group.broken_to_multilines = Some(should_break);
+        Format::Concat(Concat(formats))
+        | Format::Indent(Indent(formats))
+        | Format::Align(Align(formats)) => formats.iter().any(analyze),
+        Format::Group(group) => should_break(group),
+        Format::IfBreak(IfBreak { broken, flat }) => analyze(broken) || analyze(flat),
+        Format::Fill(Fill(items)) => items.iter().any(analyze),
+        // Written on the current line anyway
+        Format::LineSuffix(_) => false,
+        Format::Text(_) | Format::Nil => false,
+    }
+}
+
+/// Whether the group must break because it contains a hard line break
+fn should_break(group: &Group) -> bool {
+    match group.broken_to_multilines.get() {
+        Some(should_break) => should_break,
+        None => {
+            let should_break = group.formats.iter().any(analyze);
+            group.broken_to_multilines.set(Some(should_break));
             should_break
         }
-        _ => false,
     }
 }
 
 #[cfg(test)]
 mod test {
     use crate::formatter::{
-        rules::{group, tag},
+        ir::{align, if_break, TextBreakKind},
+        rules::{fill, group, indent, line_suffix, list, new_line, tag, text_break},
         writer::COUNTER,
-        Writer,
+        Config, Format, IndentStyle, Writer,
     };
 
+    fn write_with_max_width(format: Format, max_width: u32) -> String {
+        let config = Config {
+            max_width,
+            ..Config::default()
+        };
+        Writer::new(config).write(format)
+    }
+
+    fn call(name: &str, arguments: &[&str]) -> Format {
+        group([
+            tag(name),
+            tag("("),
+            text_break(0, TextBreakKind::Open),
+            list(itertools::intersperse(
+                arguments.iter().map(tag),
+                list([tag(","), text_break(1, TextBreakKind::Same)]),
+            )),
+            text_break(0, TextBreakKind::Close),
+            tag(")"),
+        ])
+    }
+
+    #[test]
+    fn keep_group_fitting_max_width_flat() {
+        let written = write_with_max_width(call("f", &["a", "b"]), 7);
+        assert_eq!(written, "f(a, b)");
+    }
+
+    #[test]
+    fn break_group_exceeding_max_width() {
+        let written = write_with_max_width(call("f", &["a", "b"]), 6);
+        assert_eq!(written, "f(\n    a,\n    b\n)");
+    }
+
+    #[test]
+    fn count_text_following_group_until_next_line_break() {
+        let format = list([
+            call("f", &["a"]),
+            tag(";"),
+            text_break(0, TextBreakKind::NewLine),
+        ]);
+        assert_eq!(write_with_max_width(format.clone(), 5), "f(a);");
+        assert_eq!(write_with_max_width(format, 4), "f(\n    a\n);");
+    }
+
+    #[test]
+    fn break_outer_group_before_inner_ones() {
+        let written = write_with_max_width(
+            group([
+                tag("["),
+                text_break(0, TextBreakKind::Open),
+                call("g", &["a", "b"]),
+                tag(","),
+                text_break(1, TextBreakKind::Same),
+                call("h", &["c"]),
+                text_break(0, TextBreakKind::Close),
+                tag("]"),
+            ]),
+            14,
+        );
+        assert_eq!(written, "[\n    g(a, b),\n    h(c)\n]");
+    }
+
+    #[test]
+    fn indent_block() {
+        let format = list([
+            tag("{"),
+            indent([new_line(), tag("a;"), new_line(), tag("b;")]),
+            new_line(),
+            tag("}"),
+        ]);
+        assert_eq!(write_with_max_width(format, 80), "{\n    a;\n    b;\n}");
+    }
+
+    #[test]
+    fn indent_block_nested_in_open_and_close() {
+        let format = group([
+            tag("{"),
+            text_break(0, TextBreakKind::Open),
+            tag("a"),
+            indent([new_line(), tag("b")]),
+            text_break(0, TextBreakKind::Close),
+            tag("}"),
+        ]);
+        assert_eq!(write_with_max_width(format, 80), "{\n    a\n        b\n}");
+    }
+
+    #[test]
+    fn align_to_starting_column() {
+        let format = list([
+            tag("prop = "),
+            align([tag("<1>,"), new_line(), tag("<2>;")]),
+        ]);
+        assert_eq!(write_with_max_width(format, 80), "prop = <1>,\n       <2>;");
+    }
+
+    #[test]
+    fn align_adds_to_indentation() {
+        let format = indent([
+            new_line(),
+            tag("a "),
+            align([tag("b"), new_line(), tag("c")]),
+        ]);
+        assert_eq!(write_with_max_width(format, 80), "\n    a b\n      c");
+    }
+
+    #[test]
+    fn indent_with_tabs_and_align_with_spaces() {
+        let format = indent([
+            new_line(),
+            tag("a "),
+            align([tag("b"), new_line(), tag("c")]),
+        ]);
+        let config = Config {
+            indent_style: IndentStyle::Tab,
+            ..Config::default()
+        };
+        assert_eq!(Writer::new(config).write(format), "\n\ta b\n\t  c");
+    }
+
+    #[test]
+    fn count_tabs_as_indent_width() {
+        let config = Config {
+            indent_style: IndentStyle::Tab,
+            max_width: 8,
+            ..Config::default()
+        };
+        let format = indent([new_line(), call("f", &["a"])]);
+        assert_eq!(Writer::new(config.clone()).write(format), "\n\tf(a)");
+        let format = indent([new_line(), call("f", &["a", "b"])]);
+        assert_eq!(
+            Writer::new(config).write(format),
+            "\n\tf(\n\t\ta,\n\t\tb\n\t)"
+        );
+    }
+
+    #[test]
+    fn if_break_follows_enclosing_group() {
+        let list = |width| {
+            let format = group([
+                tag("["),
+                text_break(0, TextBreakKind::Open),
+                tag("a,"),
+                text_break(1, TextBreakKind::Same),
+                tag("b"),
+                if_break(tag(","), tag("")),
+                text_break(0, TextBreakKind::Close),
+                tag("]"),
+            ]);
+            write_with_max_width(format, width)
+        };
+        assert_eq!(list(80), "[a, b]");
+        assert_eq!(list(4), "[\n    a,\n    b,\n]");
+    }
+
+    #[test]
+    fn fill_lines_with_items() {
+        let items = ["aa", "bb", "cc", "dd", "ee"].map(tag);
+        let format = group([fill(items, text_break(1, TextBreakKind::Same))]);
+        assert_eq!(write_with_max_width(format.clone(), 80), "aa bb cc dd ee");
+        assert_eq!(write_with_max_width(format, 8), "aa bb cc\ndd ee");
+    }
+
+    #[test]
+    fn fill_breaks_items_that_do_not_fit() {
+        let items = [tag("a"), call("f", &["b", "c"]), tag("d")];
+        let format = group([fill(items, text_break(1, TextBreakKind::Same))]);
+        assert_eq!(
+            write_with_max_width(format, 5),
+            "a\nf(\n    b,\n    c\n)\nd"
+        );
+    }
+
+    #[test]
+    fn fill_breaks_after_items_with_hard_line_breaks() {
+        let items = [
+            tag("aa"),
+            list([tag("bb"), line_suffix([tag(" // bb")]), new_line()]),
+            tag("cc"),
+            tag("dd"),
+        ];
+        let format = group([fill(items, text_break(1, TextBreakKind::Same))]);
+        assert_eq!(write_with_max_width(format, 80), "aa bb // bb\ncc dd");
+    }
+
+    #[test]
+    fn line_suffix_before_next_new_line() {
+        let format = list([
+            tag("a;"),
+            line_suffix([tag(" // comment")]),
+            tag(" b;"),
+            new_line(),
+            tag("c;"),
+        ]);
+        assert_eq!(write_with_max_width(format, 80), "a; b; // comment\nc;");
+    }
+
+    #[test]
+    fn trim_spaces_before_line_breaks() {
+        let format = list([
+            tag("a"),
+            tag(" "),
+            new_line(),
+            tag("b"),
+            tag(" \t"),
+        ]);
+        assert_eq!(write_with_max_width(format, 80), "a\nb");
+    }
+
+    #[test]
+    fn line_suffix_is_not_measured() {
+        let format = list([
+            call("f", &["a"]),
+            line_suffix([tag(" // long comment")]),
+            new_line(),
+        ]);
+        assert_eq!(write_with_max_width(format, 4), "f(a) // long comment");
+    }
+
     #[test]
     fn test_analyze_performance() {
         let mut test_format = tag("testing");
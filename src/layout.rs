@@ -0,0 +1,362 @@
+//! Physical layouts of keyboards.
+//!
+//! A [`Layout`] places every key of a board on a grid of rows and columns,
+//! in the order of the keymap bindings, so split halves, gaps and thumb clusters
+//! can be reflected in the formatted bindings. Grid columns no key sits on
+//! are written as gaps.
+
+use std::{collections::BTreeMap, error::Error, fmt::Display};
+
+use serde::Deserialize;
+
+use crate::{
+    ast::{
+        ArrayCell, ArrayValue, AstNode, Document, MacroCell, NodeDefinition, NodeIdentifier,
+        PropertyDefinition, PropertyValue,
+    },
+    lexer::TokenKind,
+};
+
+/// Position of a key on the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KeyPosition {
+    pub row: usize,
+    pub column: usize,
+}
+
+/// Positions of the keys of a board, in the order of the keymap bindings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    keys: Vec<KeyPosition>,
+}
+
+impl Layout {
+    pub fn new(keys: Vec<KeyPosition>) -> Self {
+        Self { keys }
+    }
+
+    /// Layout from the number of keys of every row, from top to bottom.
+    /// A row of a split board lists the keys of its left and right halves:
+    /// left halves are aligned to the split point, which is after the widest left half,
+    /// and right halves start `split_gap` columns after it.
+    /// A row listing a single number isn't split, its keys start at the first column.
+    pub fn from_rows(rows: &[Vec<usize>], split_gap: usize) -> Result<Self, LayoutError> {
+        let split = rows
+            .iter()
+            .filter(|row| row.len() == 2)
+            .map(|row| row[0])
+            .max()
+            .unwrap_or(0);
+        let mut keys = Vec::new();
+        for (row, lengths) in rows.iter().enumerate() {
+            let columns = match lengths[..] {
+                [length] => 0..length,
+                [left, _] => split - left..split,
+                _ => {
+                    return Err(LayoutError::new(format!(
+                        "row {} should list the keys of the whole row, or of its two halves, found {} numbers",
+                        row + 1,
+                        lengths.len()
+                    )))
+                }
+            };
+            keys.extend(columns.map(|column| KeyPosition { row, column }));
+            if let [_, right] = lengths[..] {
+                let start = split + split_gap;
+                keys.extend((start..start + right).map(|column| KeyPosition { row, column }));
+            }
+        }
+        Ok(Self { keys })
+    }
+
+    /// Layout from a QMK `info.json`, using its layout called `name`,
+    /// which can be left out if it only has one.
+    /// Keys are put on the rows and columns closest to their `x` and `y` coordinates,
+    /// rounding down the rows to keep staggered columns on their row.
+    pub fn from_qmk_info(json: &str, name: Option<&str>) -> Result<Self, LayoutError> {
+        let info: QmkInfo = serde_json::from_str(json)
+            .map_err(|error| LayoutError::new(format!("invalid QMK info.json: {error}")))?;
+        let layout = match name {
+            Some(name) => info
+                .layouts
+                .get(name)
+                .ok_or_else(|| LayoutError::new(format!("no layout `{name}` in QMK info.json")))?,
+            None if info.layouts.len() == 1 => info.layouts.values().next().unwrap(),
+            None => {
+                let names = info.layouts.keys().cloned().collect::<Vec<_>>().join(", ");
+                return Err(LayoutError::new(format!(
+                    "QMK info.json should have exactly one layout, or the layout to use should be named, found: {names}"
+                )));
+            }
+        };
+
+        let mut rows: BTreeMap<usize, Vec<(f64, usize)>> = BTreeMap::new();
+        for (index, key) in layout.layout.iter().enumerate() {
+            rows.entry(key.y.max(0.0).floor() as usize)
+                .or_default()
+                .push((key.x, index));
+        }
+        let mut keys = vec![KeyPosition { row: 0, column: 0 }; layout.layout.len()];
+        for (row, (_, mut row_keys)) in rows.into_iter().enumerate() {
+            row_keys.sort_by(|(x, _), (other_x, _)| x.total_cmp(other_x));
+            // Keys closer than a column apart still get their own column
+            let mut next_column = 0;
+            for (x, index) in row_keys {
+                let column = (x.max(0.0).round() as usize).max(next_column);
+                keys[index] = KeyPosition { row, column };
+                next_column = column + 1;
+            }
+        }
+        Ok(Self { keys })
+    }
+
+    /// Layout of the keys of the `zmk,matrix-transform` node of `document`,
+    /// the one the `chosen` node picks if there are many.
+    /// Rows are the lines of its `map`, see [`matrix_transform_lines`].
+    /// Columns are the ones of the `RC()` cells, unless they don't increase along a row.
+    pub fn from_matrix_transform(document: &Document) -> Option<Self> {
+        let mut chosen = None;
+        let mut transforms = Vec::new();
+        for node in document.syntax().descendants() {
+            let Some(node) = NodeDefinition::cast(&node) else {
+                continue;
+            };
+            if node_name(&node).as_deref() == Some("chosen") {
+                chosen = chosen_transform(&node).or(chosen);
+            }
+            if node.is_compatible("zmk,matrix-transform") {
+                transforms.push(node);
+            }
+        }
+        let transform = chosen
+            .and_then(|chosen| {
+                transforms.iter().find(|transform| {
+                    transform.label().is_some_and(|label| {
+                        label.syntax().text().trim_end_matches(':').trim_end() == chosen
+                    })
+                })
+            })
+            .or(transforms.first())?;
+        let map = transform.properties().find(|property| {
+            property
+                .name()
+                .is_ok_and(|name| name.syntax().text() == "map")
+        })?;
+        let PropertyDefinition::NonBool(map) = map else {
+            return None;
+        };
+        let Some(PropertyValue::Array(map)) = map.values().ok()?.values().into_iter().next() else {
+            return None;
+        };
+
+        let mut keys = Vec::new();
+        for (row, line) in matrix_transform_lines(&map)?.into_iter().enumerate() {
+            let increasing = line.windows(2).all(|pair| pair[0].1 < pair[1].1);
+            keys.extend(line.iter().enumerate().map(|(index, (_, column, _))| {
+                let column = if increasing { *column } else { index };
+                KeyPosition { row, column }
+            }));
+        }
+        Some(Self { keys })
+    }
+
+    /// Bundled layout of a popular board, by name
+    pub fn preset(name: &str) -> Option<Self> {
+        PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .map(|(_, rows)| Self::from_grid(rows))
+    }
+
+    pub fn preset_names() -> impl Iterator<Item = &'static str> {
+        PRESETS.iter().map(|(name, _)| *name)
+    }
+
+    /// Layout drawn as rows of `x` for keys and `.` for empty columns
+    fn from_grid(rows: &[&str]) -> Self {
+        let keys = rows
+            .iter()
+            .enumerate()
+            .flat_map(|(row, columns)| {
+                columns
+                    .char_indices()
+                    .filter(|(_, column)| *column == 'x')
+                    .map(move |(column, _)| KeyPosition { row, column })
+            })
+            .collect();
+        Self { keys }
+    }
+
+    pub fn keys(&self) -> &[KeyPosition] {
+        &self.keys
+    }
+}
+
+/// Keys in the order of the boards' ZMK keymaps, split halves separated by an empty column
+const PRESETS: &[(&str, &[&str])] = &[
+    (
+        "glove80",
+        &[
+            "xxxxx.........xxxxx",
+            "xxxxxx.......xxxxxx",
+            "xxxxxx.......xxxxxx",
+            "xxxxxx.......xxxxxx",
+            "xxxxxxxxx.xxxxxxxxx",
+            "xxxxx.xxx.xxx.xxxxx",
+        ],
+    ),
+    (
+        "corne",
+        &[
+            "xxxxxx.xxxxxx",
+            "xxxxxx.xxxxxx",
+            "xxxxxx.xxxxxx",
+            "...xxx.xxx...",
+        ],
+    ),
+    (
+        "sofle",
+        &[
+            "xxxxxx...xxxxxx",
+            "xxxxxx...xxxxxx",
+            "xxxxxx...xxxxxx",
+            "xxxxxxx.xxxxxxx",
+            ".xxxxx...xxxxx.",
+        ],
+    ),
+    (
+        "lily58",
+        &[
+            "xxxxxx...xxxxxx",
+            "xxxxxx...xxxxxx",
+            "xxxxxx...xxxxxx",
+            "xxxxxxx.xxxxxxx",
+            "...xxxx.xxxx...",
+        ],
+    ),
+    (
+        "kyria",
+        &[
+            "xxxxxx.....xxxxxx",
+            "xxxxxx.....xxxxxx",
+            "xxxxxxxx.xxxxxxxx",
+            "...xxxxx.xxxxx...",
+        ],
+    ),
+    (
+        "ferris",
+        &["xxxxx.xxxxx", "xxxxx.xxxxx", "xxxxx.xxxxx", "...xx.xx..."],
+    ),
+];
+
+fn node_name(node: &NodeDefinition) -> Option<String> {
+    match node.identifier().ok()? {
+        NodeIdentifier::NonRoot(identifier) => Some(identifier.name().ok()?.syntax().text()),
+        NodeIdentifier::Root(_) => None,
+    }
+}
+
+/// Label of the matrix transform picked by `zmk,matrix-transform = &label;`,
+/// which is also found when wrapped in `<>`
+fn chosen_transform(chosen: &NodeDefinition) -> Option<String> {
+    let property = chosen.properties().find(|property| {
+        property
+            .name()
+            .is_ok_and(|name| name.syntax().text() == "zmk,matrix-transform")
+    })?;
+    property.syntax().descendants().find_map(|node| {
+        let tokens = node
+            .children()
+            .into_iter()
+            .filter_map(|child| child.as_token().cloned())
+            .collect::<Vec<_>>();
+        tokens.windows(2).find_map(|pair| {
+            (pair[0].kind() == TokenKind::AMP && pair[1].kind() == TokenKind::NAME)
+                .then(|| pair[1].text().to_owned())
+        })
+    })
+}
+
+/// Row, column and text of the `RC(row, column)` cells of a matrix transform `map`,
+/// split into the lines the user wrote them on. A map written on a single line
+/// is split where the matrix row changes.
+pub(crate) fn matrix_transform_lines(
+    map: &ArrayValue,
+) -> Option<Vec<Vec<(usize, usize, MacroCell)>>> {
+    let cells = map.cells().into_iter().collect::<Vec<_>>();
+    let single_line = !cells.iter().skip(1).any(is_on_new_line);
+    let mut lines: Vec<Vec<(usize, usize, MacroCell)>> = Vec::new();
+    for cell in cells {
+        let ArrayCell::Macro(macro_cell) = &cell else {
+            return None;
+        };
+        let (row, column) = parse_rc(&macro_cell.syntax().text())?;
+        let starts_line = match lines.last().and_then(|line| line.last()) {
+            None => true,
+            Some((previous_row, _, _)) if single_line => *previous_row != row,
+            Some(_) => is_on_new_line(&cell),
+        };
+        if starts_line {
+            lines.push(Vec::new());
+        }
+        lines.last_mut()?.push((row, column, macro_cell.clone()));
+    }
+    Some(lines)
+}
+
+fn is_on_new_line(cell: &ArrayCell) -> bool {
+    cell.syntax().first_token().is_some_and(|token| {
+        token
+            .leading_trivia()
+            .iter()
+            .any(|trivia| trivia.is_newline())
+    })
+}
+
+/// Row and column of `RC(row, column)`
+fn parse_rc(text: &str) -> Option<(usize, usize)> {
+    let arguments = text
+        .strip_prefix("RC")?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+    let (row, column) = arguments.split_once(',')?;
+    Some((row.trim().parse().ok()?, column.trim().parse().ok()?))
+}
+
+#[derive(Deserialize)]
+struct QmkInfo {
+    layouts: BTreeMap<String, QmkLayout>,
+}
+
+#[derive(Deserialize)]
+struct QmkLayout {
+    layout: Vec<QmkKey>,
+}
+
+#[derive(Deserialize)]
+struct QmkKey {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutError {
+    pub msg: String,
+}
+
+impl LayoutError {
+    fn new(msg: impl ToString) -> Self {
+        Self {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl Error for LayoutError {}
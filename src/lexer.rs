@@ -6,18 +6,27 @@ use crate::source::{Source, SourceIndex, SourceRange};
 pub(crate) struct Lexer<'src> {
     source: &'src Source<'src>,
     current_position: SourceIndex,
+    diagnostics: Vec<LexError>,
 }
 impl<'src> Lexer<'src> {
     pub(crate) fn new(source: &'src Source<'src>) -> Self {
         Self {
             source,
             current_position: SourceIndex::default(),
+            diagnostics: Vec::new(),
         }
     }
 
+    pub(crate) fn finish(self) -> Vec<LexError> {
+        self.diagnostics
+    }
+
     pub(crate) fn next_token(&mut self) -> Token {
         if self.is_eof() {
-            return Token::eof();
+            return Token {
+                kind: TokenKind::EOF,
+                range: self.range(self.current_position),
+            };
         }
         match self.current_byte() {
             b'&' => self.consume_single_byte(TokenKind::AMP),
@@ -41,10 +50,17 @@ impl<'src> Lexer<'src> {
             b'a'..=b'z' | b'A'..=b'Z' | b'#' => self.consume_name(),
             b' ' | b'\t' => self.consume_whitespace(),
             b'\r' | b'\n' => self.consume_new_line(),
-            _ => self.consume_single_byte(TokenKind::UNKNOWN),
+            _ => self.consume_unknown_char(),
         }
     }
 
+    fn report(&mut self, msg: impl ToString, range: SourceRange) {
+        self.diagnostics.push(LexError {
+            msg: msg.to_string(),
+            range,
+        })
+    }
+
     fn advance(&mut self) {
         self.current_position = self.current_position.increment();
     }
@@ -66,6 +82,29 @@ impl<'src> Lexer<'src> {
         }
     }
 
+    // Non-ASCII characters can only be part of strings and comments,
+    // consume the whole character to stay on a UTF-8 boundary
+    fn consume_unknown_char(&mut self) -> Token {
+        let start = self.current_position;
+        let c = self
+            .source
+            .char_at(start)
+            .expect("Lexer must stay on a character boundary");
+        for _ in 0..c.len_utf8() {
+            self.advance();
+        }
+        let range = self.range(start);
+        if c == '\u{feff}' && start == SourceIndex::default() {
+            self.report("Byte order mark is not supported", range);
+        } else {
+            self.report(format!("Unexpected character {c:?}"), range);
+        }
+        Token {
+            kind: TokenKind::UNKNOWN,
+            range,
+        }
+    }
+
     fn consume_slash(&mut self) -> Token {
         assert_eq!(self.current_byte(), b'/');
         match (self.current_byte(), self.peek()) {
@@ -82,7 +121,7 @@ impl<'src> Lexer<'src> {
         self.advance();
         loop {
             self.advance();
-            if self.is_eof() || self.current_byte() == b'\n' {
+            if self.is_eof() || self.at_new_line() {
                 break Token {
                     kind: TokenKind::S_COMMENT,
                     range: self.range(start),
@@ -100,9 +139,11 @@ impl<'src> Lexer<'src> {
             self.advance();
 
             if self.is_eof() {
+                let range = self.range(start);
+                self.report("Unterminated block comment", range);
                 break Token {
                     kind: TokenKind::UNKNOWN,
-                    range: self.range(start),
+                    range,
                 };
             }
             if self.current_byte() == b'*' && self.peek().is_some_and(|t| t == b'/') {
@@ -133,13 +174,19 @@ impl<'src> Lexer<'src> {
         let start = self.current_position;
         self.advance();
         while !self.is_eof() && self.current_byte() != b'"' && self.current_byte() != b'\n' {
+            // Skip escaped characters, like \"
+            if self.current_byte() == b'\\' && self.peek().is_some_and(|b| b != b'\n') {
+                self.advance();
+            }
             self.advance();
         }
         if self.is_eof() || self.current_byte() == b'\n' {
             // Don't consume the new line, as new line is not part of a string
+            let range = self.range(start);
+            self.report("Unterminated string", range);
             Token {
                 kind: TokenKind::UNKNOWN,
-                range: self.range(start),
+                range,
             }
         } else {
             self.advance();
@@ -230,9 +277,11 @@ impl<'src> Lexer<'src> {
             }
             _ => {
                 self.advance();
+                let range = self.range(start);
+                self.report("Unexpected character '\\r'", range);
                 Token {
                     kind: TokenKind::UNKNOWN,
-                    range: self.range(start),
+                    range,
                 }
             }
         }
@@ -242,11 +291,26 @@ impl<'src> Lexer<'src> {
         self.source.is_eof(self.current_position)
     }
 
+    /// At `\n` or `\r\n`
+    fn at_new_line(&self) -> bool {
+        match self.current_byte() {
+            b'\n' => true,
+            b'\r' => self.peek() == Some(b'\n'),
+            _ => false,
+        }
+    }
+
     fn range(&self, start: SourceIndex) -> SourceRange {
         SourceRange::new(start, self.current_position)
     }
 }
 
+#[derive(Debug)]
+pub(crate) struct LexError {
+    pub(crate) msg: String,
+    pub(crate) range: SourceRange,
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct Token {
     pub(crate) kind: TokenKind,
@@ -269,6 +333,10 @@ impl Token {
         matches!(self.kind, TokenKind::B_COMMENT)
     }
 
+    pub(crate) fn is_comment(&self) -> bool {
+        self.is_single_line_comment() || self.is_block_comment()
+    }
+
     pub(crate) fn is_newline(&self) -> bool {
         matches!(self.kind, TokenKind::NEW_LINE)
     }
@@ -276,19 +344,12 @@ impl Token {
     pub(crate) fn is_eof(&self) -> bool {
         matches!(self.kind, TokenKind::EOF)
     }
-
-    fn eof() -> Self {
-        Self {
-            kind: TokenKind::EOF,
-            range: SourceRange::null(),
-        }
-    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[allow(non_camel_case_types)]
 #[allow(clippy::upper_case_acronyms)]
-pub(crate) enum TokenKind {
+pub enum TokenKind {
     /// Ampersand `@`
     AMP,
     /// At sign `@`
@@ -341,11 +402,19 @@ impl fmt::Display for TokenKind {
     }
 }
 
+/// A non-trivia token, together with the trivia around it
+#[derive(Clone, Debug)]
+pub(crate) struct LexedToken {
+    pub(crate) token: Token,
+    pub(crate) leading_trivia: Vec<Token>,
+    pub(crate) trailing_trivia: Vec<Token>,
+}
+
 pub(crate) struct BufferedLexer<'src> {
-    cached_next_tokens: VecDeque<Token>,
+    cached_next_tokens: VecDeque<LexedToken>,
     lexer: Lexer<'src>,
-    last_token_range: SourceRange,
-    tokens: Vec<Token>,
+    // Token lexed ahead while looking for the end of the trailing trivia
+    pending_token: Option<Token>,
 }
 
 impl<'src> BufferedLexer<'src> {
@@ -353,58 +422,79 @@ impl<'src> BufferedLexer<'src> {
         Self {
             cached_next_tokens: VecDeque::new(),
             lexer,
-            last_token_range: SourceRange::null(),
-            tokens: Vec::new(),
+            pending_token: None,
         }
     }
+
+    pub(crate) fn source(&self) -> &'src Source<'src> {
+        self.lexer.source
+    }
+
+    pub(crate) fn finish(self) -> Vec<LexError> {
+        self.lexer.finish()
+    }
 }
 
 impl BufferedLexer<'_> {
     pub(crate) fn nth(&mut self, pos: usize) -> Token {
         self.populate_cache(pos + 1);
-        self.cached_next_tokens.get(pos).unwrap().clone()
+        self.cached_next_tokens.get(pos).unwrap().token.clone()
     }
 
-    pub(crate) fn advance(&mut self) -> Token {
-        let token = if self.cached_next_tokens.is_empty() {
-            self.next_non_trivia_token()
+    pub(crate) fn advance(&mut self) -> LexedToken {
+        if self.cached_next_tokens.is_empty() {
+            self.next_lexed_token()
         } else {
             self.cached_next_tokens.pop_front().unwrap()
-        };
-        self.last_token_range = token.range;
-        token
-    }
-
-    pub(crate) fn current_token_start(&mut self) -> SourceIndex {
-        self.current_token_range().start()
-    }
-
-    pub(crate) fn last_token_end(&mut self) -> SourceIndex {
-        self.last_token_range.end()
-    }
-
-    pub(crate) fn finish(self) -> Vec<Token> {
-        self.tokens
+        }
     }
 
     pub(crate) fn current_token_range(&mut self) -> SourceRange {
         self.populate_cache(1);
-        self.cached_next_tokens.front().unwrap().range
+        self.cached_next_tokens.front().unwrap().token.range
     }
 
-    fn next_non_trivia_token(&mut self) -> Token {
-        loop {
-            let token = self.lexer.next_token();
-            self.tokens.push(token.clone());
+    /// Lex the next non-trivia token.
+    /// Trivia up to the next new line is its trailing trivia,
+    /// everything before it, up to the previous token's trailing trivia, is its leading trivia.
+    fn next_lexed_token(&mut self) -> LexedToken {
+        let mut leading_trivia = Vec::new();
+        let token = loop {
+            let token = self.next_token();
             if !token.is_trivia() {
                 break token;
             }
+            leading_trivia.push(token);
+        };
+        let mut trailing_trivia = Vec::new();
+        if !token.is_eof() {
+            loop {
+                let next_token = self.next_token();
+                if next_token.is_trivia() && !next_token.is_newline() {
+                    trailing_trivia.push(next_token);
+                } else {
+                    self.pending_token = Some(next_token);
+                    break;
+                }
+            }
+        }
+        LexedToken {
+            token,
+            leading_trivia,
+            trailing_trivia,
+        }
+    }
+
+    fn next_token(&mut self) -> Token {
+        if let Some(token) = self.pending_token.take() {
+            return token;
         }
+        self.lexer.next_token()
     }
 
     fn populate_cache(&mut self, size: usize) {
         while self.cached_next_tokens.len() < size {
-            let token = self.next_non_trivia_token();
+            let token = self.next_lexed_token();
             self.cached_next_tokens.push_back(token);
         }
     }
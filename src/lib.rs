@@ -1,23 +1,179 @@
-mod ast;
+pub mod ast;
+mod config_file;
+mod edit;
+mod editorconfig;
 mod formatter;
+mod layout;
 mod lexer;
+mod line_index;
 mod parser;
+mod query;
 mod source;
-mod token_source;
+pub mod syntax;
+pub mod visitor;
 use std::{error::Error, fs};
 
-use source::Source;
+use ast::AstNode;
+use formatter::{Divergence, FormatError, Instability};
+
+pub use ast::Document;
+pub use config_file::{
+    discover_config, parse_config, read_config, read_layout, ConfigError, CONFIG_FILE_NAME,
+};
+pub use formatter::{Config, EndOfLine, IndentStyle};
+pub use layout::{KeyPosition, Layout, LayoutError};
+pub use line_index::{ColumnUnit, LineCol, LineIndex};
+pub use parser::{parse, reparse, ParseError, TextEdit};
+pub use query::{Query, QueryError, QueryMatch};
+pub use source::{Source, SourceIndex, SourceRange};
 
 #[cfg(test)]
 mod tests;
 
 pub fn format(in_path: &str, out_path: &str) -> Result<(), Box<dyn Error>> {
-    let file = fs::read_to_string(in_path)?;
+    format_with_config(in_path, out_path, &Config::default())
+}
+
+pub fn format_with_config(
+    in_path: &str,
+    out_path: &str,
+    config: &Config,
+) -> Result<(), Box<dyn Error>> {
+    let file = read_source(in_path)?;
     let file_str = file.as_str();
 
     let source = Source::new(file_str);
-    let (doc, token_source, _) = parser::parse(&source);
-    let formatted = formatter::format(doc, &source, token_source);
+    let (doc, diagnostics) = parser::parse(&source);
+    // Don't nuke user's file with syntax errors
+    check_diagnostics(in_path, &source, &diagnostics)?;
+    let formatted = formatter::format(doc, &source, config)
+        .map_err(|error| format_error(in_path, &source, error))?;
+    fs::write(out_path, formatted)?;
+    Ok(())
+}
+
+/// Format only the nodes and properties overlapping `range`, a range of bytes of the file.
+/// The rest of the file is written as it is.
+pub fn format_range_with_config(
+    in_path: &str,
+    out_path: &str,
+    range: SourceRange,
+    config: &Config,
+) -> Result<(), Box<dyn Error>> {
+    let file = read_source(in_path)?;
+    if range.start() > range.end() || range.end().value() > file.len() {
+        return Err(format!("{in_path}: range {range} is outside of the file").into());
+    }
+
+    let source = Source::new(&file);
+    let (doc, diagnostics) = parser::parse(&source);
+    check_diagnostics(in_path, &source, &diagnostics)?;
+    let formatted = formatter::format_range(doc, &source, range, config)
+        .map_err(|error| format_error(in_path, &source, error))?;
     fs::write(out_path, formatted)?;
     Ok(())
 }
+
+/// Run `query` on the file at `path`,
+/// describing every match by its position and its text
+pub fn query_file(path: &str, query: &Query) -> Result<Vec<String>, Box<dyn Error>> {
+    let file = read_source(path)?;
+    let source = Source::new(&file);
+    let (doc, diagnostics) = parser::parse(&source);
+    check_diagnostics(path, &source, &diagnostics)?;
+    let matches = query
+        .matches(&doc)
+        .iter()
+        .map(|query_match| {
+            let position = source
+                .line_index()
+                .line_col(query_match.range().start(), ColumnUnit::Utf8);
+            diagnostic_message(path, position, &query_match.syntax().text())
+        })
+        .collect();
+    Ok(matches)
+}
+
+fn check_diagnostics(
+    path: &str,
+    source: &Source,
+    diagnostics: &[ParseError],
+) -> Result<(), Box<dyn Error>> {
+    if diagnostics.is_empty() {
+        return Ok(());
+    }
+    let messages = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let position = source
+                .line_index()
+                .line_col(diagnostic.range.start(), ColumnUnit::Utf8);
+            diagnostic_message(path, position, &diagnostic.msg)
+        })
+        .collect::<Vec<_>>();
+    Err(messages.join("\n").into())
+}
+
+/// Format a parsed, and possibly edited, document.
+/// The document's text is returned as it is if it has syntax errors,
+/// or if formatting would lose a comment.
+pub fn format_document(doc: &Document) -> String {
+    let text = doc.syntax().to_string();
+    let source = Source::new(&text);
+    let (_, diagnostics) = parser::parse(&source);
+    if !diagnostics.is_empty() {
+        return text;
+    }
+    formatter::format(doc.clone(), &source, &Config::default()).unwrap_or(text)
+}
+
+fn format_error(path: &str, source: &Source, error: FormatError) -> Box<dyn Error> {
+    match error {
+        FormatError::MissingSyntax => {
+            format!("{path}: The document has syntax errors; the file is left unchanged").into()
+        }
+        FormatError::MissingComment(range) => {
+            let position = source
+                .line_index()
+                .line_col(range.start(), ColumnUnit::Utf8);
+            diagnostic_message(
+                path,
+                position,
+                "Formatting would drop this comment, the file is left unchanged",
+            )
+            .into()
+        }
+        FormatError::Diverged(Divergence { position, msg }) => {
+            let position = source.line_index().line_col(position, ColumnUnit::Utf8);
+            let msg = format!(
+                "The formatted text differs from the source, {msg}; the file is left unchanged"
+            );
+            diagnostic_message(path, position, &msg).into()
+        }
+        FormatError::Unstable(Instability {
+            line,
+            formatted,
+            reformatted,
+        }) => format!(
+            "{path}: Formatting again changes line {} of the formatted text, \
+             `{formatted}` becomes `{reformatted}`; the file is left unchanged",
+            line + 1
+        )
+        .into(),
+    }
+}
+
+fn read_source(path: &str) -> Result<String, Box<dyn Error>> {
+    let bytes = fs::read(path)?;
+    String::from_utf8(bytes).map_err(|error| {
+        let valid_up_to = error.utf8_error().valid_up_to();
+        let valid = std::str::from_utf8(&error.as_bytes()[..valid_up_to]).unwrap();
+        let position =
+            LineIndex::new(valid).line_col(SourceIndex::new(valid_up_to), ColumnUnit::Utf8);
+        diagnostic_message(path, position, "Invalid UTF-8 sequence").into()
+    })
+}
+
+fn diagnostic_message(path: &str, LineCol { line, col }: LineCol, msg: &str) -> String {
+    format!("{path}:{}:{}: {msg}", line + 1, col + 1)
+}
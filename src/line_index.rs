@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use crate::source::{SourceIndex, SourceRange};
+
+/// Zero based line and column of a position in the source
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct LineCol {
+    pub line: u32,
+    pub col: u32,
+}
+
+/// Unit of a column
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnUnit {
+    /// Bytes of the UTF-8 encoded line
+    Utf8,
+    /// Code units of the UTF-16 encoded line, used by LSP clients
+    Utf16,
+}
+
+/// Conversion between byte offsets and line/column pairs
+#[derive(Debug)]
+pub struct LineIndex {
+    /// Offset of the start of each line
+    line_starts: Vec<usize>,
+    /// Offset of the end of the text
+    len: usize,
+    /// Multi-byte characters of each line, in order
+    wide_chars: HashMap<u32, Vec<WideChar>>,
+}
+
+/// A multi-byte character, positioned by its UTF-8 column
+#[derive(Clone, Copy, Debug)]
+struct WideChar {
+    start: u32,
+    end: u32,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![0];
+        let mut wide_chars: HashMap<u32, Vec<WideChar>> = HashMap::new();
+        let mut line = 0;
+        for (offset, c) in text.char_indices() {
+            if c == '\n' {
+                line_starts.push(offset + 1);
+                line += 1;
+            } else if c.len_utf8() > 1 {
+                let start = (offset - line_starts[line as usize]) as u32;
+                wide_chars.entry(line).or_default().push(WideChar {
+                    start,
+                    end: start + c.len_utf8() as u32,
+                });
+            }
+        }
+        Self {
+            line_starts,
+            len: text.len(),
+            wide_chars,
+        }
+    }
+
+    pub fn line_col(&self, index: SourceIndex, unit: ColumnUnit) -> LineCol {
+        let offset = index.value().min(self.len);
+        let line = self
+            .line_starts
+            .partition_point(|&line_start| line_start <= offset)
+            - 1;
+        let col = (offset - self.line_starts[line]) as u32;
+        let line = line as u32;
+        let col = match unit {
+            ColumnUnit::Utf8 => col,
+            ColumnUnit::Utf16 => self.utf8_to_utf16_col(line, col),
+        };
+        LineCol { line, col }
+    }
+
+    /// Offset of a line/column pair, `None` if the line doesn't exist,
+    /// the column is past the end of the line or inside a character
+    pub fn offset(&self, line_col: LineCol, unit: ColumnUnit) -> Option<SourceIndex> {
+        let line_start = *self.line_starts.get(line_col.line as usize)?;
+        let line_end = self
+            .line_starts
+            .get(line_col.line as usize + 1)
+            .map_or(self.len, |next_line_start| next_line_start - 1);
+        let col = match unit {
+            ColumnUnit::Utf8 => line_col.col,
+            ColumnUnit::Utf16 => self.utf16_to_utf8_col(line_col.line, line_col.col),
+        };
+        let inside_char = self
+            .line_wide_chars(line_col.line)
+            .iter()
+            .any(|c| c.start < col && col < c.end);
+        let offset = line_start + col as usize;
+        (offset <= line_end && !inside_char).then_some(SourceIndex::new(offset))
+    }
+
+    /// Range from the start of the `first` line to the end of the `last` one,
+    /// including its line break, `None` if the `first` line doesn't exist
+    pub fn lines_range(&self, first: u32, last: u32) -> Option<SourceRange> {
+        let start = *self.line_starts.get(first as usize)?;
+        let end = self
+            .line_starts
+            .get(last.max(first) as usize + 1)
+            .map_or(self.len, |next_line_start| *next_line_start);
+        Some(SourceRange::new(
+            SourceIndex::new(start),
+            SourceIndex::new(end),
+        ))
+    }
+
+    fn utf8_to_utf16_col(&self, line: u32, col: u32) -> u32 {
+        let mut utf16_col = col;
+        for c in self.line_wide_chars(line) {
+            if c.end > col {
+                break;
+            }
+            utf16_col -= c.len() - c.utf16_len();
+        }
+        utf16_col
+    }
+
+    fn utf16_to_utf8_col(&self, line: u32, col: u32) -> u32 {
+        let mut utf8_col = col;
+        for c in self.line_wide_chars(line) {
+            if c.start >= utf8_col {
+                break;
+            }
+            utf8_col += c.len() - c.utf16_len();
+        }
+        utf8_col
+    }
+
+    fn line_wide_chars(&self, line: u32) -> &[WideChar] {
+        self.wide_chars.get(&line).map_or(&[], Vec::as_slice)
+    }
+}
+
+impl WideChar {
+    fn len(&self) -> u32 {
+        self.end - self.start
+    }
+
+    fn utf16_len(&self) -> u32 {
+        if self.len() == 4 {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::source::{Source, SourceIndex};
+
+    use super::{ColumnUnit, LineCol, LineIndex};
+
+    #[test]
+    fn get_line_number() {
+        let source = Source::new(
+            r#"/* Glove80 system behavior & macros */ / {
+    behaviors {
+        // For the "layer" key, it'd nice to be able to use it as either a shift or a toggle.
+        lower: lower {
+            compatible = "zmk,behavior-tap-dance";
+            label = "LAYER_TAP_DANCE";
+            #binding-cells = <0>;
+            tapping-term-ms = <200>;
+        };
+    };
+};
+        "#,
+        );
+        let compatible = source.to_string().find("compatible").unwrap();
+        assert_eq!(
+            source
+                .line_index()
+                .line_col(SourceIndex::new(compatible), ColumnUnit::Utf8),
+            LineCol { line: 4, col: 12 }
+        );
+    }
+
+    #[test]
+    fn convert_between_offset_and_line_col() {
+        let text = "a\r\nbc\n\nd";
+        let index = LineIndex::new(text);
+        let expected = [
+            (0, 0),
+            (0, 1),
+            (0, 2),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (2, 0),
+            (3, 0),
+            (3, 1),
+        ];
+        for (offset, (line, col)) in expected.into_iter().enumerate() {
+            let line_col = LineCol { line, col };
+            let offset = SourceIndex::new(offset);
+            assert_eq!(index.line_col(offset, ColumnUnit::Utf8), line_col);
+            assert_eq!(index.offset(line_col, ColumnUnit::Utf8), Some(offset));
+        }
+        assert_eq!(
+            index.offset(LineCol { line: 1, col: 3 }, ColumnUnit::Utf8),
+            None
+        );
+        assert_eq!(
+            index.offset(LineCol { line: 4, col: 0 }, ColumnUnit::Utf8),
+            None
+        );
+    }
+
+    #[test]
+    fn get_range_of_lines() {
+        let index = LineIndex::new("a\r\nbc\n\nd");
+        let range = |first, last| {
+            index
+                .lines_range(first, last)
+                .map(|range| (range.start().value(), range.end().value()))
+        };
+        assert_eq!(range(0, 0), Some((0, 3)));
+        assert_eq!(range(1, 2), Some((3, 7)));
+        assert_eq!(range(2, 10), Some((6, 8)));
+        assert_eq!(range(4, 5), None);
+    }
+
+    #[test]
+    fn convert_utf16_columns() {
+        // 'é' is 2 bytes and 1 UTF-16 code unit, '😀' is 4 bytes and 2 UTF-16 code units
+        let text = "x\nlabel = \"é😀\"; // ok";
+        let index = LineIndex::new(text);
+        let quote = text.rfind('"').unwrap();
+        let line_col = index.line_col(SourceIndex::new(quote), ColumnUnit::Utf16);
+        assert_eq!(line_col, LineCol { line: 1, col: 12 });
+        assert_eq!(
+            index.line_col(SourceIndex::new(quote), ColumnUnit::Utf8),
+            LineCol { line: 1, col: 15 }
+        );
+        assert_eq!(
+            index.offset(line_col, ColumnUnit::Utf16),
+            Some(SourceIndex::new(quote))
+        );
+        for col in 0..=9 {
+            let line_col = LineCol { line: 1, col };
+            let offset = index.offset(line_col, ColumnUnit::Utf16).unwrap();
+            assert_eq!(index.line_col(offset, ColumnUnit::Utf16), line_col);
+        }
+    }
+
+    #[test]
+    fn reject_columns_inside_characters() {
+        let text = "label = \"é😀\";";
+        let index = LineIndex::new(text);
+        let e = text.find('é').unwrap() as u32;
+        let offset = |col, unit| index.offset(LineCol { line: 0, col }, unit);
+        assert_eq!(
+            offset(e, ColumnUnit::Utf8),
+            Some(SourceIndex::new(e as usize))
+        );
+        assert_eq!(offset(e + 1, ColumnUnit::Utf8), None);
+        assert_eq!(
+            offset(e + 2, ColumnUnit::Utf8),
+            Some(SourceIndex::new(e as usize + 2))
+        );
+        assert_eq!(offset(e + 3, ColumnUnit::Utf8), None);
+        // Between the two UTF-16 code units of '😀'
+        assert_eq!(offset(e + 2, ColumnUnit::Utf16), None);
+    }
+}
@@ -1,7 +1,144 @@
-use std::error::Error;
+use std::{error::Error, fs, path::Path, process::ExitCode};
 
-use zmk_styler::format;
+use clap::{Parser, Subcommand};
+use zmk_styler::{
+    discover_config, format_range_with_config, format_with_config, query_file, read_config,
+    read_layout, LineIndex, Query, SourceIndex, SourceRange,
+};
 
-fn main() -> Result<(), Box<dyn Error>> {
-    format("glove80.keymap", "formatted.keymap")
+#[derive(Parser)]
+#[command(version, about = "Formatter for ZMK keymaps and devicetree files")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Format a file, in place unless an output file is given.
+    /// Options are read from the `zmk-styler.toml` files of its directories,
+    /// or else from their `.editorconfig` files.
+    Format {
+        input: String,
+        output: Option<String>,
+        /// Config file to use instead of the ones of the input's directories
+        #[arg(long, value_name = "ZMK_STYLER_TOML")]
+        config: Option<String>,
+        /// Physical layout the keymap bindings are laid out by: a bundled one
+        /// (glove80, corne, sofle, lily58, kyria or ferris), or a QMK `info.json`
+        #[arg(long, value_name = "PRESET_OR_INFO_JSON")]
+        layout: Option<String>,
+        /// Layout of the `info.json` to use, when it has more than one
+        #[arg(long, requires = "layout")]
+        layout_name: Option<String>,
+        /// Format only the nodes and properties overlapping a range of bytes, like `120:480`
+        #[arg(long, value_name = "START:END", value_parser = parse_range, conflicts_with = "lines")]
+        range: Option<(usize, usize)>,
+        /// Format only the nodes and properties overlapping a range of lines, like `10-40`
+        #[arg(long, value_name = "FIRST-LAST", value_parser = parse_lines)]
+        lines: Option<(u32, u32)>,
+        /// Parse the formatted text again, and leave the file unchanged
+        /// unless its tokens and comments are the ones of the input
+        #[arg(long)]
+        verify: bool,
+        /// Format the formatted text again, and leave the file unchanged
+        /// unless formatting it is a no-op
+        #[arg(long)]
+        check_idempotency: bool,
+    },
+    /// Print the nodes and properties selected by a query, like `/keymap/*/bindings`
+    Query {
+        selector: String,
+        #[arg(required = true)]
+        files: Vec<String>,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(command: Command) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Format {
+            input,
+            output,
+            config,
+            layout,
+            layout_name,
+            range,
+            lines,
+            verify,
+            check_idempotency,
+        } => {
+            let mut config = match config {
+                Some(config) => read_config(Path::new(&config))?,
+                None => discover_config(Path::new(&input))?,
+            };
+            if let Some(layout) = layout {
+                config.layout = Some(read_layout(&layout, layout_name.as_deref(), Path::new(""))?);
+            }
+            config.verify |= verify;
+            config.check_idempotency |= check_idempotency;
+            let output = output.as_deref().unwrap_or(&input);
+            let range = match (range, lines) {
+                (Some((start, end)), _) => {
+                    SourceRange::new(SourceIndex::new(start), SourceIndex::new(end))
+                }
+                (None, Some((first, last))) => {
+                    let text = fs::read_to_string(&input)
+                        .map_err(|error| format!("Cannot read `{input}`: {error}"))?;
+                    LineIndex::new(&text)
+                        .lines_range(first - 1, last - 1)
+                        .ok_or_else(|| format!("{input}: line {first} is outside of the file"))?
+                }
+                (None, None) => return format_with_config(&input, output, &config),
+            };
+            format_range_with_config(&input, output, range, &config)
+        }
+        Command::Query { selector, files } => {
+            let query = Query::new(&selector)
+                .map_err(|error| format!("Invalid query `{selector}`: {error}"))?;
+            for file in files {
+                for query_match in query_file(&file, &query)? {
+                    println!("{query_match}");
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Byte offsets of `--range`, like `120:480`
+fn parse_range(value: &str) -> Result<(usize, usize), String> {
+    let invalid = || format!("`{value}` isn't a range of bytes like `120:480`");
+    let (start, end) = value.split_once(':').ok_or_else(invalid)?;
+    let start = start.trim().parse::<usize>().map_err(|_| invalid())?;
+    let end = end.trim().parse::<usize>().map_err(|_| invalid())?;
+    if start > end {
+        return Err(format!("`{value}` ends before it starts"));
+    }
+    Ok((start, end))
+}
+
+/// One-based, inclusive line numbers of `--lines`, like `10-40`
+fn parse_lines(value: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("`{value}` isn't a range of lines like `10-40`");
+    let (first, last) = value.split_once('-').ok_or_else(invalid)?;
+    let first = first.trim().parse::<u32>().map_err(|_| invalid())?;
+    let last = last.trim().parse::<u32>().map_err(|_| invalid())?;
+    if first == 0 {
+        return Err("lines are numbered from 1".to_owned());
+    }
+    if first > last {
+        return Err(format!("`{value}` ends before it starts"));
+    }
+    Ok((first, last))
 }
@@ -7,7 +7,7 @@ use super::{
 };
 
 pub(crate) fn parse_document(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     parse_list(
         p,
         parse_statement,
@@ -16,7 +16,8 @@ pub(crate) fn parse_document(p: &mut Parser) {
         None,
         is_statement_recovered,
     );
-    p.end(start, SyntaxKind::Document)
+    p.bump(TokenKind::EOF);
+    p.end(SyntaxKind::Document)
 }
 
 fn parse_statement(p: &mut Parser) {
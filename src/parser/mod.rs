@@ -1,24 +1,36 @@
 use core::panic;
-use std::cell::{Cell, RefCell};
+use std::{
+    cell::{Cell, RefCell},
+    error::Error,
+    fmt::Display,
+};
 
 use document::parse_document;
 
 use crate::{
-    ast::{AstNode, Document, SyntaxKind, SyntaxNodeBuilder},
-    lexer::{BufferedLexer, Lexer, Token, TokenKind},
-    source::{Source, SourceIndex, SourceRange},
-    token_source::TokenSource,
+    ast::{AstNode, Document},
+    lexer::{BufferedLexer, LexedToken, Lexer, Token, TokenKind},
+    source::{Source, SourceRange},
+    syntax::{GreenNode, GreenNodeBuilder, GreenTokenData, SyntaxKind, SyntaxNode},
 };
 
 mod document;
 mod node;
 mod property;
+mod reparse;
 mod utils;
 
+#[cfg(test)]
+pub(crate) use reparse::reparse_incrementally;
+pub use reparse::{reparse, TextEdit};
+
+// Maximum number of lookups without consuming any token
+const STUCK_THRESHOLD: u32 = 200;
+
 pub(crate) struct Parser<'src> {
     lexer: RefCell<BufferedLexer<'src>>,
     stuck_threshold: Cell<u32>,
-    nodes: Vec<SyntaxNodeBuilder>,
+    nodes: Vec<GreenNodeBuilder>,
     diasnostics: Vec<ParseError>,
 }
 
@@ -26,7 +38,7 @@ impl<'src> Parser<'src> {
     pub(crate) fn new(lexer: BufferedLexer<'src>) -> Self {
         Self {
             lexer: RefCell::new(lexer),
-            stuck_threshold: Cell::new(200),
+            stuck_threshold: Cell::new(STUCK_THRESHOLD),
             nodes: Vec::new(),
             diasnostics: Vec::new(),
         }
@@ -75,30 +87,24 @@ impl<'src> Parser<'src> {
         let lexer = self.lexer.get_mut();
         let token = lexer.advance();
 
-        assert_eq!(token.kind, kind);
-        let current_node = self.nodes.last_mut().unwrap();
-        current_node.push_token(token);
+        assert_eq!(token.token.kind, kind);
+        self.push_token(token);
     }
 
     pub(super) fn bump_any(&mut self) {
         let lexer = self.lexer.get_mut();
         let token = lexer.advance();
 
-        let current_node = self.nodes.last_mut().unwrap();
-        current_node.push_token(token);
+        self.push_token(token);
     }
 
-    pub(super) fn start(&mut self) -> SourceIndex {
-        self.nodes.push(SyntaxNodeBuilder::new());
-        self.lexer.borrow_mut().current_token_start()
+    pub(super) fn start(&mut self) {
+        self.nodes.push(GreenNodeBuilder::new());
     }
 
-    pub(super) fn end(&mut self, start: SourceIndex, kind: SyntaxKind) {
-        let end = self.lexer.borrow_mut().last_token_end();
-        let node_range = SourceRange::new(start, end);
+    pub(super) fn end(&mut self, kind: SyntaxKind) {
         let mut current_node = self.nodes.pop().unwrap();
         current_node.kind(kind);
-        current_node.range(node_range);
         if let Some(parent) = self.nodes.last_mut() {
             parent.push_node(current_node.build());
         } else {
@@ -106,19 +112,51 @@ impl<'src> Parser<'src> {
         }
     }
 
-    pub(super) fn finish(mut self) -> (Document, TokenSource, Vec<ParseError>) {
+    pub(super) fn finish(mut self) -> (Document, Vec<ParseError>) {
         assert_eq!(self.nodes.len(), 1);
-        let token_source = TokenSource::new(self.lexer.into_inner().finish());
-        let root = self.nodes.pop().unwrap();
-        (
-            Document::cast(&root.build()).unwrap(),
-            token_source,
-            self.diasnostics,
-        )
+        let root = SyntaxNode::new_root(self.nodes.pop().unwrap().build());
+        let lex_errors = self.lexer.into_inner().finish();
+        let mut diagnostics = self.diasnostics;
+        diagnostics.extend(
+            lex_errors
+                .into_iter()
+                .map(|error| ParseError::new(error.msg, error.range)),
+        );
+        // Lexer runs ahead of the parser, keep diagnostics in source order
+        diagnostics.sort_by_key(|diagnostic| diagnostic.range.start());
+        (Document::cast(&root).unwrap(), diagnostics)
+    }
+
+    /// Finish parsing a fragment of a document.
+    /// The fragment must span the whole source, without errors.
+    pub(super) fn finish_fragment(mut self) -> Option<GreenNode> {
+        let eof = self.lexer.get_mut().advance();
+        if !eof.token.is_eof() || !eof.leading_trivia.is_empty() || !self.diasnostics.is_empty() {
+            return None;
+        }
+        if !self.lexer.into_inner().finish().is_empty() {
+            return None;
+        }
+        assert_eq!(self.nodes.len(), 1);
+        Some(self.nodes.pop().unwrap().build())
+    }
+}
+
+impl Parser<'_> {
+    fn push_token(&mut self, token: LexedToken) {
+        self.stuck_threshold.set(STUCK_THRESHOLD);
+        let token = GreenTokenData::from_lexed(
+            &token.token,
+            &token.leading_trivia,
+            &token.trailing_trivia,
+            self.lexer.get_mut().source(),
+        );
+        let current_node = self.nodes.last_mut().unwrap();
+        current_node.push_token(token);
     }
 }
 
-pub(crate) fn parse(source: &Source) -> (Document, TokenSource, Vec<ParseError>) {
+pub fn parse(source: &Source) -> (Document, Vec<ParseError>) {
     let lexer = Lexer::new(source);
     let lexer = BufferedLexer::new(lexer);
     let mut parser = Parser::new(lexer);
@@ -127,10 +165,10 @@ pub(crate) fn parse(source: &Source) -> (Document, TokenSource, Vec<ParseError>)
     parser.finish()
 }
 
-#[derive(Debug)]
-pub(crate) struct ParseError {
-    pub(crate) msg: String,
-    pub(crate) range: SourceRange,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub msg: String,
+    pub range: SourceRange,
 }
 
 impl ParseError {
@@ -141,3 +179,11 @@ impl ParseError {
         }
     }
 }
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.msg, self.range)
+    }
+}
+
+impl Error for ParseError {}
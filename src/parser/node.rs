@@ -5,21 +5,21 @@ use super::{property::parse_property, utils::parse_list, Parser, SyntaxKind};
 pub(super) const END_OF_NODE_SET: [TokenKind; 2] = [TokenKind::R_CURLY, TokenKind::COLON];
 
 pub(super) fn parse_node(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     parse_label(p);
     parse_node_identifier(p);
     parse_node_body(p);
-    p.end(start, SyntaxKind::NodeDefinition)
+    p.end(SyntaxKind::NodeDefinition)
 }
 
 fn parse_label(p: &mut Parser) {
-    if !p.nth_at(1, TokenKind::COLON) {
+    if !is_at_label(p) {
         return;
     }
-    let start = p.start();
+    p.start();
     p.expect(TokenKind::NAME);
     p.expect(TokenKind::COLON);
-    p.end(start, SyntaxKind::Label)
+    p.end(SyntaxKind::Label)
 }
 
 fn parse_node_identifier(p: &mut Parser) {
@@ -31,49 +31,49 @@ fn parse_node_identifier(p: &mut Parser) {
 }
 
 fn parse_root_node_identifier(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     p.expect(TokenKind::ROOT);
-    p.end(start, SyntaxKind::RootNodeIdentifier)
+    p.end(SyntaxKind::RootNodeIdentifier)
 }
 
 fn parse_non_root_node_identifier(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     parse_node_name(p);
     parse_node_address(p);
-    p.end(start, SyntaxKind::NonRootNodeIdentifier)
+    p.end(SyntaxKind::NonRootNodeIdentifier)
 }
 
 fn parse_node_name(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     p.expect(TokenKind::NAME);
-    p.end(start, SyntaxKind::NodeName)
+    p.end(SyntaxKind::NodeName)
 }
 
 fn parse_node_address(p: &mut Parser) {
     if !p.at(TokenKind::AT) {
         return;
     }
-    let start = p.start();
+    p.start();
     p.bump(TokenKind::AT);
     if p.at(TokenKind::INT) {
         p.bump(TokenKind::INT)
     } else {
         p.expect(TokenKind::NAME)
     };
-    p.end(start, SyntaxKind::NodeAddress)
+    p.end(SyntaxKind::NodeAddress)
 }
 
-fn parse_node_body(p: &mut Parser) {
-    let start = p.start();
+pub(super) fn parse_node_body(p: &mut Parser) {
+    p.start();
     p.expect(TokenKind::L_CURLY);
     parse_node_body_entries(p);
     p.expect(TokenKind::R_CURLY);
     p.expect(TokenKind::SEMICOLON);
-    p.end(start, SyntaxKind::NodeBody)
+    p.end(SyntaxKind::NodeBody)
 }
 
 fn parse_node_body_entries(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     parse_list(
         p,
         parse_node_body_entry,
@@ -82,7 +82,7 @@ fn parse_node_body_entries(p: &mut Parser) {
         None,
         is_node_body_entry_recovered,
     );
-    p.end(start, SyntaxKind::NodeBodyEntries)
+    p.end(SyntaxKind::NodeBodyEntries)
 }
 
 fn parse_node_body_entry(p: &mut Parser) {
@@ -102,7 +102,7 @@ pub(super) fn is_at_node(p: &Parser) -> bool {
 }
 
 fn is_at_label(p: &Parser) -> bool {
-    p.nth_at(1, TokenKind::COLON)
+    p.at(TokenKind::NAME) && p.nth_at(1, TokenKind::COLON)
 }
 
 fn is_at_node_property(p: &Parser) -> bool {
@@ -12,29 +12,29 @@ pub(crate) fn parse_property(p: &mut Parser) {
 }
 
 fn parse_boolean_property(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     parse_property_name(p);
     p.expect(TokenKind::SEMICOLON);
-    p.end(start, SyntaxKind::BoolPropertyDefinition)
+    p.end(SyntaxKind::BoolPropertyDefinition)
 }
 
 fn parse_non_bool_property(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     parse_property_name(p);
     p.expect(TokenKind::EQUAL);
     parse_property_values(p);
-    p.end(start, SyntaxKind::NonBoolPropertyDefinition)
+    p.end(SyntaxKind::NonBoolPropertyDefinition)
 }
 
 fn parse_property_name(p: &mut Parser) {
-    let start = p.start();
+    p.start();
 
     p.expect(TokenKind::NAME);
-    p.end(start, SyntaxKind::PropertyName)
+    p.end(SyntaxKind::PropertyName)
 }
 
 fn parse_property_values(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     parse_list(
         p,
         parse_property_value,
@@ -44,7 +44,7 @@ fn parse_property_values(p: &mut Parser) {
         is_at_property_value_recovered,
     );
     p.expect(TokenKind::SEMICOLON);
-    p.end(start, SyntaxKind::PropertyValues)
+    p.end(SyntaxKind::PropertyValues)
 }
 
 fn parse_property_value(p: &mut Parser) {
@@ -56,7 +56,7 @@ fn parse_property_value(p: &mut Parser) {
 }
 
 fn parse_array_value(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     p.expect(TokenKind::L_ANGLE);
     parse_list(
         p,
@@ -67,23 +67,64 @@ fn parse_array_value(p: &mut Parser) {
         is_at_array_cell_recovered,
     );
     p.expect(TokenKind::R_ANGLE);
-    p.end(start, SyntaxKind::ArrayValue)
+    p.end(SyntaxKind::ArrayValue)
 }
 
 fn parse_array_cell(p: &mut Parser) {
-    parse_int_cell(p)
+    match p.current_token_kind() {
+        TokenKind::INT => parse_int_cell(p),
+        TokenKind::AMP => parse_phandle_cell(p),
+        TokenKind::NAME => parse_macro_cell(p),
+        _ => {}
+    }
 }
 
 fn parse_int_cell(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     p.bump(TokenKind::INT);
-    p.end(start, SyntaxKind::IntCell)
+    p.end(SyntaxKind::IntCell)
+}
+
+// &label
+fn parse_phandle_cell(p: &mut Parser) {
+    p.start();
+    p.bump(TokenKind::AMP);
+    p.expect(TokenKind::NAME);
+    p.end(SyntaxKind::PhandleCell)
+}
+
+// Preprocessor macros, like `Q` or `RC(0,1)`.
+// Arguments are kept as they are, since we don't expand macros
+fn parse_macro_cell(p: &mut Parser) {
+    p.start();
+    p.bump(TokenKind::NAME);
+    if p.at(TokenKind::L_PAREN) {
+        parse_macro_arguments(p);
+    }
+    p.end(SyntaxKind::MacroCell)
+}
+
+fn parse_macro_arguments(p: &mut Parser) {
+    p.bump(TokenKind::L_PAREN);
+    let mut depth = 0;
+    while !p.at_any(&[TokenKind::R_ANGLE, TokenKind::SEMICOLON, TokenKind::EOF]) {
+        if p.at(TokenKind::L_PAREN) {
+            depth += 1;
+        } else if p.at(TokenKind::R_PAREN) {
+            if depth == 0 {
+                break;
+            }
+            depth -= 1;
+        }
+        p.bump_any();
+    }
+    p.expect(TokenKind::R_PAREN);
 }
 
 fn parse_string_value(p: &mut Parser) {
-    let start = p.start();
+    p.start();
     p.expect(TokenKind::STRING);
-    p.end(start, SyntaxKind::StringValue)
+    p.end(SyntaxKind::StringValue)
 }
 
 fn is_at_property_value(p: &Parser) -> bool {
@@ -94,7 +135,10 @@ fn is_at_property_value(p: &Parser) -> bool {
 }
 
 fn is_at_array_cell(p: &Parser) -> bool {
-    p.at(TokenKind::INT)
+    matches!(
+        p.current_token_kind(),
+        TokenKind::INT | TokenKind::AMP | TokenKind::NAME
+    )
 }
 
 fn is_at_property_value_recovered(p: &Parser) -> bool {
@@ -0,0 +1,178 @@
+use crate::{
+    ast::{AstNode, Document},
+    lexer::{BufferedLexer, Lexer, TokenKind},
+    source::{Source, SourceIndex, SourceRange},
+    syntax::{GreenChild, GreenNode, SyntaxKind, SyntaxNode},
+};
+
+use super::{node::parse_node_body, parse, ParseError, Parser};
+
+/// Replace the text at `range` with `replacement`
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    pub range: SourceRange,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    pub fn new(range: SourceRange, replacement: impl ToString) -> Self {
+        Self {
+            range,
+            replacement: replacement.to_string(),
+        }
+    }
+
+    pub(crate) fn apply(&self, text: &str) -> String {
+        let mut edited = String::from(&text[..self.range.start().value()]);
+        edited.push_str(&self.replacement);
+        edited.push_str(&text[self.range.end().value()..]);
+        edited
+    }
+
+    /// Change in length of the text after the edit is applied
+    fn delta(&self) -> isize {
+        self.replacement.len() as isize
+            - (self.range.end().value() - self.range.start().value()) as isize
+    }
+}
+
+/// Apply `edit` to a parsed document.
+///
+/// Only the innermost node body containing the edit is reparsed, the rest of the tree is reused.
+/// When the edit can't be contained in a node body, e.g. it unbalances the curly brackets,
+/// the whole document is reparsed instead.
+/// Either way, the result is identical to a full reparse of the edited text.
+///
+/// # Panics
+///
+/// Panics if the range of `edit` is reversed or goes past the end of the document,
+/// or if it doesn't start and end on char boundaries
+pub fn reparse(
+    doc: &Document,
+    diagnostics: &[ParseError],
+    edit: &TextEdit,
+) -> (Document, Vec<ParseError>) {
+    let len = doc.syntax().full_range().end();
+    assert!(
+        edit.range.start() <= edit.range.end() && edit.range.end() <= len,
+        "Edit range {} is outside of the document",
+        edit.range
+    );
+    if let Some((doc, reparsed_range)) = reparse_incrementally(doc, edit) {
+        let diagnostics = diagnostics
+            .iter()
+            .filter_map(|diagnostic| shift_diagnostic(diagnostic, reparsed_range, edit))
+            .collect();
+        return (doc, diagnostics);
+    }
+    let text = edit.apply(&doc.syntax().to_string());
+    parse(&Source::new(&text))
+}
+
+/// Reparse the innermost node body containing `edit`.
+/// Returns the new document, and the range of the reparsed node body before the edit.
+pub(crate) fn reparse_incrementally(
+    doc: &Document,
+    edit: &TextEdit,
+) -> Option<(Document, SourceRange)> {
+    let (green, reparsed_range) = reparse_child_node_body(doc.syntax().green(), 0, edit)?;
+    Document::cast(&SyntaxNode::new_root(green)).map(|doc| (doc, reparsed_range))
+}
+
+fn reparse_child_node_body(
+    node: &GreenNode,
+    offset: usize,
+    edit: &TextEdit,
+) -> Option<(GreenNode, SourceRange)> {
+    let mut child_offset = offset;
+    for (index, child) in node.children().iter().enumerate() {
+        let child_end = child_offset + child.width();
+        if let GreenChild::Node(child_node) = child {
+            if child_offset <= edit.range.start().value() && edit.range.end().value() <= child_end {
+                // Prefer the innermost node body
+                let reparsed = reparse_child_node_body(child_node, child_offset, edit)
+                    .or_else(|| reparse_node_body(child_node, child_offset, edit));
+                if let Some((new_child, reparsed_range)) = reparsed {
+                    let new_node = node.replace_child(index, GreenChild::Node(new_child));
+                    return Some((new_node, reparsed_range));
+                }
+            }
+        }
+        child_offset = child_end;
+    }
+    None
+}
+
+fn reparse_node_body(
+    body: &GreenNode,
+    offset: usize,
+    edit: &TextEdit,
+) -> Option<(GreenNode, SourceRange)> {
+    if body.kind() != SyntaxKind::NodeBody {
+        return None;
+    }
+    let (l_curly_end, r_curly_start) = curly_brackets_position(body)?;
+    let start = edit.range.start().value().checked_sub(offset)?;
+    let end = edit.range.end().value() - offset;
+    // The edit must not touch the brackets, nor the trivia before `{` and after `}`,
+    // which may belong to the neighbour tokens after a full reparse
+    if start < l_curly_end || end > r_curly_start {
+        return None;
+    }
+    let relative_edit = TextEdit::new(
+        SourceRange::new(SourceIndex::new(start), SourceIndex::new(end)),
+        &edit.replacement,
+    );
+    let text = relative_edit.apply(&body.to_string());
+    let source = Source::new(&text);
+    let mut parser = Parser::new(BufferedLexer::new(Lexer::new(&source)));
+    parse_node_body(&mut parser);
+    let new_body = parser.finish_fragment()?;
+    let reparsed_range = SourceRange::new(
+        SourceIndex::new(offset),
+        SourceIndex::new(offset + body.width()),
+    );
+    Some((new_body, reparsed_range))
+}
+
+/// Relative positions of the end of `{` and the start of `}` in a node body
+fn curly_brackets_position(body: &GreenNode) -> Option<(usize, usize)> {
+    let mut l_curly_end = None;
+    let mut r_curly_start = None;
+    let mut offset = 0;
+    for child in body.children() {
+        if let GreenChild::Token(token) = child {
+            let token_start = offset + token.leading_trivia_width();
+            match token.kind() {
+                TokenKind::L_CURLY => l_curly_end = Some(token_start + token.text().len()),
+                TokenKind::R_CURLY => r_curly_start = Some(token_start),
+                _ => {}
+            }
+        }
+        offset += child.width();
+    }
+    Some((l_curly_end?, r_curly_start?))
+}
+
+/// Move a diagnostic of the original document to its place in the edited document,
+/// dropping the ones inside the reparsed node body
+fn shift_diagnostic(
+    diagnostic: &ParseError,
+    reparsed_range: SourceRange,
+    edit: &TextEdit,
+) -> Option<ParseError> {
+    let range = diagnostic.range;
+    if range.end() <= reparsed_range.start() {
+        Some(diagnostic.clone())
+    } else if range.start() >= reparsed_range.end() {
+        let shift = |index: SourceIndex| {
+            SourceIndex::new(index.value().checked_add_signed(edit.delta()).unwrap())
+        };
+        Some(ParseError {
+            msg: diagnostic.msg.clone(),
+            range: SourceRange::new(shift(range.start()), shift(range.end())),
+        })
+    } else {
+        None
+    }
+}
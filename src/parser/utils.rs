@@ -30,6 +30,9 @@ pub(super) fn parse_list<F, C, R>(
         if is_at_element(p) {
             element_parser(p);
         } else {
+            // Skip at least one token, or the parser would be stuck
+            // when the unexpected token is also a recovery point
+            p.bump_any();
             while !is_recovered(p) && !p.at(TokenKind::EOF) {
                 p.bump_any();
             }
@@ -0,0 +1,450 @@
+//! Select nodes and properties of a document with path-like selectors.
+//!
+//! A selector is a list of steps separated by `/`:
+//! - `/keymap/*/bindings`: a leading `/` starts from the root nodes,
+//!   every step then selects children of the nodes selected by the previous one.
+//! - `bindings`: without a leading `/`, the first step selects nodes and properties at any depth.
+//! - `**` makes the next step select descendants instead of children, like `/**/bindings`.
+//!
+//! A step selects nodes and properties by name, or nodes by label:
+//! - `keymap` or `*_layer`, where `*` matches any characters. Nodes are matched with
+//!   and without their address, so `key@1` is selected by `key`, `key@1` or `key@*`.
+//! - `&td` selects the node labeled `td`.
+//!
+//! A step can be followed by filters, only keeping nodes that have some property:
+//! - `[hold-trigger-on-release]`: the node has the property.
+//! - `[compatible="zmk,behavior-hold-tap"]`: one of the property's strings is the quoted value.
+//! - `[#binding-cells=<0>]`: the property's values are written as the unquoted value,
+//!   white spaces aside.
+//!
+//! A step can also be made of filters alone, like `[compatible="zmk,keymap"]`, selecting any node.
+
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    ast::{
+        AstNode, Document, NodeBodyEntry, NodeDefinition, NodeIdentifier, PropertyDefinition,
+        PropertyValue, Statement,
+    },
+    source::SourceRange,
+    syntax::SyntaxNode,
+};
+
+/// A parsed selector
+#[derive(Debug, Clone)]
+pub struct Query {
+    absolute: bool,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    axis: Axis,
+    test: NameTest,
+    filters: Vec<Filter>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Child,
+    Descendant,
+}
+
+#[derive(Debug, Clone)]
+enum NameTest {
+    /// Any node or property
+    Any,
+    /// Nodes or properties with a name matching the pattern
+    Name(String),
+    /// Nodes with the label
+    Label(String),
+}
+
+#[derive(Debug, Clone)]
+struct Filter {
+    property: String,
+    value: Option<FilterValue>,
+}
+
+#[derive(Debug, Clone)]
+enum FilterValue {
+    /// `"value"`, compared with the content of string values
+    String(String),
+    /// `value`, compared with the source text of all values
+    Raw(String),
+}
+
+/// A node or a property selected by a query
+#[derive(Debug, Clone)]
+pub enum QueryMatch {
+    Node(NodeDefinition),
+    Property(PropertyDefinition),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub msg: String,
+    /// Byte offset of the error in the selector
+    pub position: usize,
+}
+
+impl Query {
+    pub fn new(selector: &str) -> Result<Self, QueryError> {
+        QueryParser::new(selector).parse()
+    }
+
+    /// Matches of the query, in document order
+    pub fn matches(&self, doc: &Document) -> Vec<QueryMatch> {
+        let mut candidates: Vec<QueryMatch> = if self.absolute {
+            root_nodes(doc).into_iter().map(QueryMatch::Node).collect()
+        } else {
+            doc.statements()
+                .into_iter()
+                .map(|Statement::Node(node)| QueryMatch::Node(node))
+                .collect()
+        };
+        for (index, step) in self.steps.iter().enumerate() {
+            // The first step of relative queries also selects top level nodes themselves
+            let include_self = !self.absolute && index == 0;
+            let mut selected = Vec::new();
+            for candidate in &candidates {
+                if include_self && step.matches(candidate) {
+                    selected.push(candidate.clone());
+                }
+                let QueryMatch::Node(node) = candidate else {
+                    continue;
+                };
+                let axis = if include_self {
+                    Axis::Descendant
+                } else {
+                    step.axis
+                };
+                collect_children(node, axis, &mut |child| {
+                    if step.matches(&child) {
+                        selected.push(child);
+                    }
+                });
+            }
+            selected.sort_by_key(|selected| selected.range().start());
+            selected.dedup_by_key(|selected| selected.range());
+            candidates = selected;
+        }
+        candidates
+    }
+}
+
+impl QueryMatch {
+    pub fn range(&self) -> SourceRange {
+        self.syntax().range()
+    }
+
+    pub fn syntax(&self) -> &SyntaxNode {
+        match self {
+            QueryMatch::Node(node) => node.syntax(),
+            QueryMatch::Property(property) => property.syntax(),
+        }
+    }
+}
+
+impl Step {
+    fn matches(&self, candidate: &QueryMatch) -> bool {
+        let name_matches = match (&self.test, candidate) {
+            (NameTest::Any, _) => true,
+            (NameTest::Name(pattern), QueryMatch::Node(node)) => {
+                node.identifier().is_ok_and(|identifier| match identifier {
+                    NodeIdentifier::Root(_) => false,
+                    NodeIdentifier::NonRoot(identifier) => {
+                        let full_name = identifier.syntax().text();
+                        let name = identifier.name().map(|name| name.syntax().text());
+                        glob_matches(pattern, &full_name)
+                            || name.is_ok_and(|name| glob_matches(pattern, &name))
+                    }
+                })
+            }
+            (NameTest::Name(pattern), QueryMatch::Property(property)) => {
+                property_name(property).is_some_and(|name| glob_matches(pattern, &name))
+            }
+            (NameTest::Label(label), QueryMatch::Node(node)) => node
+                .label()
+                .is_some_and(|node_label| label_name(&node_label.syntax().text()) == label),
+            (NameTest::Label(_), QueryMatch::Property(_)) => false,
+        };
+        name_matches && self.filters.iter().all(|filter| filter.matches(candidate))
+    }
+}
+
+impl Filter {
+    fn matches(&self, candidate: &QueryMatch) -> bool {
+        let QueryMatch::Node(node) = candidate else {
+            return false;
+        };
+        let mut properties = Vec::new();
+        collect_children(node, Axis::Child, &mut |child| {
+            if let QueryMatch::Property(property) = child {
+                properties.push(property);
+            }
+        });
+        properties.iter().any(|property| {
+            property_name(property).is_some_and(|name| name == self.property)
+                && self
+                    .value
+                    .as_ref()
+                    .is_none_or(|value| value.matches(property))
+        })
+    }
+}
+
+impl FilterValue {
+    fn matches(&self, property: &PropertyDefinition) -> bool {
+        let PropertyDefinition::NonBool(property) = property else {
+            return false;
+        };
+        let Ok(values) = property.values() else {
+            return false;
+        };
+        let values = values.values().into_iter().collect::<Vec<_>>();
+        match self {
+            FilterValue::String(expected) => values.iter().any(|value| match value {
+                PropertyValue::String(string) => {
+                    let text = string.syntax().text();
+                    text.get(1..text.len() - 1) == Some(expected.as_str())
+                }
+                PropertyValue::Array(_) => false,
+            }),
+            FilterValue::Raw(expected) => {
+                let text = values
+                    .iter()
+                    .map(|value| value.syntax().text())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                &normalize_whitespaces(&text) == expected
+            }
+        }
+    }
+}
+
+fn root_nodes(doc: &Document) -> Vec<NodeDefinition> {
+    doc.statements()
+        .into_iter()
+        .map(|Statement::Node(node)| node)
+        .filter(|node| matches!(node.identifier(), Ok(NodeIdentifier::Root(_))))
+        .collect()
+}
+
+/// Call `f` with every child, or descendant, node and property of `node`
+fn collect_children(node: &NodeDefinition, axis: Axis, f: &mut impl FnMut(QueryMatch)) {
+    let Ok(entries) = node.body().and_then(|body| body.entries()) else {
+        return;
+    };
+    for entry in entries.entries() {
+        match entry {
+            NodeBodyEntry::Node(child) => {
+                f(QueryMatch::Node(child.clone()));
+                if axis == Axis::Descendant {
+                    collect_children(&child, axis, f);
+                }
+            }
+            NodeBodyEntry::Property(property) => f(QueryMatch::Property(property)),
+        }
+    }
+}
+
+fn property_name(property: &PropertyDefinition) -> Option<String> {
+    property.name().ok().map(|name| name.syntax().text())
+}
+
+/// Label text without the trailing `:`
+fn label_name(label: &str) -> &str {
+    label.trim_end_matches(':').trim_end()
+}
+
+/// Whether `text` matches `pattern`, where `*` matches any characters
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=text.len())
+                .filter(|&start| text.is_char_boundary(start))
+                .any(|start| glob_matches(rest, &text[start..]))
+        }
+    }
+}
+
+struct QueryParser<'a> {
+    selector: &'a str,
+    position: usize,
+}
+
+impl<'a> QueryParser<'a> {
+    fn new(selector: &'a str) -> Self {
+        Self {
+            selector,
+            position: 0,
+        }
+    }
+
+    fn parse(mut self) -> Result<Query, QueryError> {
+        let absolute = self.eat('/');
+        let mut steps = Vec::new();
+        if absolute && self.is_eof() {
+            return Ok(Query { absolute, steps });
+        }
+        let mut axis = Axis::Child;
+        loop {
+            if self.rest() == "**" || self.rest().starts_with("**/") {
+                self.position += 2;
+                if self.is_eof() {
+                    return Err(self.error("Expected a step after `**`"));
+                }
+                self.expect('/')?;
+                axis = Axis::Descendant;
+                continue;
+            }
+            let test = self.parse_name_test()?;
+            let filters = self.parse_filters()?;
+            let test = match test {
+                Some(test) => test,
+                None if !filters.is_empty() => NameTest::Any,
+                None => return Err(self.error("Expected a name, a label or a filter")),
+            };
+            steps.push(Step {
+                axis,
+                test,
+                filters,
+            });
+            axis = Axis::Child;
+            if self.is_eof() {
+                break;
+            }
+            self.expect('/')?;
+        }
+        Ok(Query { absolute, steps })
+    }
+
+    fn parse_name_test(&mut self) -> Result<Option<NameTest>, QueryError> {
+        if self.eat('&') {
+            let label = self.take_while(|c| !matches!(c, '/' | '['));
+            if label.is_empty() {
+                return Err(self.error("Expected a label after `&`"));
+            }
+            return Ok(Some(NameTest::Label(label.to_owned())));
+        }
+        let name = self.take_while(|c| !matches!(c, '/' | '[' | ']'));
+        Ok(match name {
+            "" => None,
+            "*" => Some(NameTest::Any),
+            name => Some(NameTest::Name(name.to_owned())),
+        })
+    }
+
+    fn parse_filters(&mut self) -> Result<Vec<Filter>, QueryError> {
+        let mut filters = Vec::new();
+        while self.eat('[') {
+            let property = self.take_while(|c| !matches!(c, '=' | ']'));
+            if property.is_empty() {
+                return Err(self.error("Expected a property name"));
+            }
+            let property = property.to_owned();
+            let value = if self.eat('=') {
+                Some(self.parse_filter_value()?)
+            } else {
+                None
+            };
+            self.expect(']')?;
+            filters.push(Filter { property, value });
+        }
+        Ok(filters)
+    }
+
+    fn parse_filter_value(&mut self) -> Result<FilterValue, QueryError> {
+        if !self.eat('"') {
+            let value = self.take_while(|c| c != ']');
+            return Ok(FilterValue::Raw(normalize_whitespaces(value)));
+        }
+        // Escapes are kept, as they are compared with the string as written in the source
+        let start = self.position;
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            if c == '"' && !escaped {
+                let value = &self.selector[start..self.position];
+                self.position += 1;
+                return Ok(FilterValue::String(value.to_owned()));
+            }
+            escaped = c == '\\' && !escaped;
+            self.position += c.len_utf8();
+        }
+        Err(self.error("Unterminated string"))
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.position;
+        while let Some(c) = self.peek().filter(|&c| predicate(c)) {
+            self.position += c.len_utf8();
+        }
+        &self.selector[start..self.position]
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), QueryError> {
+        if self.eat(expected) {
+            return Ok(());
+        }
+        let msg = match self.peek() {
+            Some(found) => format!("Expected `{expected}`, but found `{found}`"),
+            None => format!("Expected `{expected}`, but found end of query"),
+        };
+        Err(self.error(msg))
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let is_at = self.peek() == Some(expected);
+        if is_at {
+            self.position += expected.len_utf8();
+        }
+        is_at
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.selector[self.position..]
+    }
+
+    fn is_eof(&self) -> bool {
+        self.position == self.selector.len()
+    }
+
+    fn error(&self, msg: impl ToString) -> QueryError {
+        QueryError {
+            msg: msg.to_string(),
+            position: self.position,
+        }
+    }
+}
+
+/// Collapse white spaces, so that `< 0 >` is written as `<0>`, and `<0  1>` as `<0 1>`
+fn normalize_whitespaces(text: &str) -> String {
+    let mut normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    for (spaced, collapsed) in [
+        ("< ", "<"),
+        (" >", ">"),
+        ("( ", "("),
+        (" )", ")"),
+        (" ,", ","),
+    ] {
+        normalized = normalized.replace(spaced, collapsed);
+    }
+    normalized
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.msg, self.position)
+    }
+}
+
+impl Error for QueryError {}
@@ -1,24 +1,34 @@
-use std::{fmt::Display, ops::Index};
+use std::{cell::OnceCell, fmt::Display, ops::Index};
 
-pub(crate) struct Source<'src> {
+use crate::line_index::LineIndex;
+
+pub struct Source<'src> {
     data: &'src str,
+    line_index: OnceCell<LineIndex>,
 }
 
 /// Range of a text object, exclusive
-#[derive(Clone, Copy, Debug)]
-pub(crate) struct SourceRange {
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SourceRange {
     start: SourceIndex,
     end: SourceIndex,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
-pub(crate) struct SourceIndex {
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct SourceIndex {
     value: usize,
 }
 
 impl<'src> Source<'src> {
-    pub(crate) fn new(src: &'src str) -> Self {
-        Self { data: src }
+    pub fn new(src: &'src str) -> Self {
+        Self {
+            data: src,
+            line_index: OnceCell::new(),
+        }
+    }
+
+    pub fn line_index(&self) -> &LineIndex {
+        self.line_index.get_or_init(|| LineIndex::new(self.data))
     }
 
     pub(crate) fn is_eof(&self, index: SourceIndex) -> bool {
@@ -28,6 +38,18 @@ impl<'src> Source<'src> {
     pub(crate) fn get(&self, index: SourceIndex) -> Option<&u8> {
         self.data.as_bytes().get(index.value)
     }
+
+    pub(crate) fn char_at(&self, index: SourceIndex) -> Option<char> {
+        self.data.get(index.value..)?.chars().next()
+    }
+
+    pub(crate) fn as_str(&self) -> &'src str {
+        self.data
+    }
+
+    pub(crate) fn text(&self, range: SourceRange) -> &'src str {
+        &self.data[range.start.value..range.end.value]
+    }
 }
 
 impl Index<SourceRange> for Source<'_> {
@@ -49,38 +71,17 @@ impl Index<SourceIndex> for Source<'_> {
 }
 
 impl SourceRange {
-    pub(crate) fn new(start: SourceIndex, end: SourceIndex) -> Self {
+    pub fn new(start: SourceIndex, end: SourceIndex) -> Self {
         SourceRange { start, end }
     }
 
-    // For when we don't need to use value of the range, like the EOF token
-    // Max value to help with binary search
-    pub(crate) fn null() -> Self {
-        SourceRange {
-            start: SourceIndex { value: usize::MAX },
-            end: SourceIndex { value: usize::MAX },
-        }
-    }
-
-    pub(crate) fn start(&self) -> SourceIndex {
+    pub fn start(&self) -> SourceIndex {
         self.start
     }
 
-    pub(crate) fn end(&self) -> SourceIndex {
+    pub fn end(&self) -> SourceIndex {
         self.end
     }
-
-    #[cfg(test)]
-    pub(crate) fn limit(self, limit: usize) -> Self {
-        Self {
-            start: SourceIndex {
-                value: self.start.value.min(limit),
-            },
-            end: SourceIndex {
-                value: self.end.value.min(limit),
-            },
-        }
-    }
 }
 
 impl From<SourceRange> for core::ops::Range<usize> {
@@ -93,6 +94,14 @@ impl From<SourceRange> for core::ops::Range<usize> {
 }
 
 impl SourceIndex {
+    pub fn new(value: usize) -> Self {
+        Self { value }
+    }
+
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
     pub(crate) fn increment(&self) -> Self {
         Self {
             value: self.value + 1,
@@ -0,0 +1,622 @@
+//! Lossless concrete syntax tree.
+//!
+//! The tree is split in two layers, following the red/green design of Roslyn and rust-analyzer:
+//! - The green tree is immutable and position independent. Every token owns its text together
+//!   with the leading and trailing trivia (white spaces, new lines and comments) around it,
+//!   so printing a green tree gives back the exact source it was parsed from.
+//! - The red tree is a thin, lazily created cursor over the green tree that knows
+//!   its absolute offset in the source.
+//!
+//! Trivia ownership follows the usual convention: a token owns every trivia following it up to,
+//! but excluding, the next new line. Everything else belongs to the next token as its leading trivia.
+//! Trivia at the end of the file is owned by the `EOF` token.
+
+use std::{fmt::Display, rc::Rc};
+
+pub use crate::lexer::TokenKind;
+use crate::{
+    lexer::Token,
+    source::{Source, SourceIndex, SourceRange},
+};
+
+pub(crate) type GreenNode = Rc<GreenNodeData>;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct GreenNodeData {
+    kind: SyntaxKind,
+    children: Vec<GreenChild>,
+    width: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum GreenChild {
+    Node(GreenNode),
+    Token(GreenToken),
+}
+
+pub(crate) type GreenToken = Rc<GreenTokenData>;
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct GreenTokenData {
+    kind: TokenKind,
+    text: String,
+    leading_trivia: Vec<GreenTrivia>,
+    trailing_trivia: Vec<GreenTrivia>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GreenTrivia {
+    kind: TokenKind,
+    text: String,
+}
+
+impl GreenNodeData {
+    pub(crate) fn new(kind: SyntaxKind, children: Vec<GreenChild>) -> GreenNode {
+        let width = children.iter().map(GreenChild::width).sum();
+        Rc::new(Self {
+            kind,
+            children,
+            width,
+        })
+    }
+
+    pub(crate) fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub(crate) fn children(&self) -> &[GreenChild] {
+        &self.children
+    }
+
+    /// Width of the node, including all trivia
+    pub(crate) fn width(&self) -> usize {
+        self.width
+    }
+
+    /// A copy of the node, with its `index`th child replaced
+    pub(crate) fn replace_child(&self, index: usize, child: GreenChild) -> GreenNode {
+        self.splice_children(index..index + 1, [child])
+    }
+
+    /// A copy of the node, with the children in `range` replaced by `children`
+    pub(crate) fn splice_children(
+        &self,
+        range: std::ops::Range<usize>,
+        children: impl IntoIterator<Item = GreenChild>,
+    ) -> GreenNode {
+        let mut new_children = self.children.clone();
+        new_children.splice(range, children);
+        Self::new(self.kind, new_children)
+    }
+
+    /// A copy of the node, with the leading trivia of its first token
+    /// and the trailing trivia of its last token replaced
+    pub(crate) fn with_boundary_trivia(
+        &self,
+        leading_trivia: Vec<GreenTrivia>,
+        trailing_trivia: Vec<GreenTrivia>,
+    ) -> GreenNode {
+        let green = self.map_first_token(&mut |token| {
+            GreenTokenData::new(
+                token.kind(),
+                token.text(),
+                leading_trivia.clone(),
+                token.trailing_trivia().to_vec(),
+            )
+        });
+        green.map_last_token(&mut |token| {
+            GreenTokenData::new(
+                token.kind(),
+                token.text(),
+                token.leading_trivia().to_vec(),
+                trailing_trivia.clone(),
+            )
+        })
+    }
+
+    pub(crate) fn first_leading_trivia(&self) -> &[GreenTrivia] {
+        self.first_token()
+            .map_or(&[], |token| token.leading_trivia())
+    }
+
+    pub(crate) fn last_trailing_trivia(&self) -> &[GreenTrivia] {
+        self.last_token()
+            .map_or(&[], |token| token.trailing_trivia())
+    }
+
+    fn map_first_token(&self, f: &mut impl FnMut(&GreenToken) -> GreenToken) -> GreenNode {
+        let Some(index) = self.children.iter().position(GreenChild::has_token) else {
+            return Self::new(self.kind, self.children.clone());
+        };
+        let child = match &self.children[index] {
+            GreenChild::Token(token) => GreenChild::Token(f(token)),
+            GreenChild::Node(node) => GreenChild::Node(node.map_first_token(f)),
+        };
+        self.replace_child(index, child)
+    }
+
+    fn map_last_token(&self, f: &mut impl FnMut(&GreenToken) -> GreenToken) -> GreenNode {
+        let Some(index) = self.children.iter().rposition(GreenChild::has_token) else {
+            return Self::new(self.kind, self.children.clone());
+        };
+        let child = match &self.children[index] {
+            GreenChild::Token(token) => GreenChild::Token(f(token)),
+            GreenChild::Node(node) => GreenChild::Node(node.map_last_token(f)),
+        };
+        self.replace_child(index, child)
+    }
+
+    fn first_token(&self) -> Option<&GreenToken> {
+        self.children.iter().find_map(|child| match child {
+            GreenChild::Token(token) => Some(token),
+            GreenChild::Node(node) => node.first_token(),
+        })
+    }
+
+    fn last_token(&self) -> Option<&GreenToken> {
+        self.children.iter().rev().find_map(|child| match child {
+            GreenChild::Token(token) => Some(token),
+            GreenChild::Node(node) => node.last_token(),
+        })
+    }
+}
+
+impl GreenChild {
+    pub(crate) fn width(&self) -> usize {
+        match self {
+            GreenChild::Node(node) => node.width(),
+            GreenChild::Token(token) => token.width(),
+        }
+    }
+
+    pub(crate) fn has_token(&self) -> bool {
+        match self {
+            GreenChild::Node(node) => node.first_token().is_some(),
+            GreenChild::Token(_) => true,
+        }
+    }
+}
+
+impl GreenTokenData {
+    pub(crate) fn new(
+        kind: TokenKind,
+        text: impl ToString,
+        leading_trivia: Vec<GreenTrivia>,
+        trailing_trivia: Vec<GreenTrivia>,
+    ) -> GreenToken {
+        Rc::new(Self {
+            kind,
+            text: text.to_string(),
+            leading_trivia,
+            trailing_trivia,
+        })
+    }
+
+    /// Build a green token out of lexed tokens, reading their text from `source`
+    pub(crate) fn from_lexed(
+        token: &Token,
+        leading_trivia: &[Token],
+        trailing_trivia: &[Token],
+        source: &Source,
+    ) -> GreenToken {
+        let to_trivia = |trivia: &Token| GreenTrivia::new(trivia.kind, source.text(trivia.range));
+        Self::new(
+            token.kind,
+            source.text(token.range),
+            leading_trivia.iter().map(to_trivia).collect(),
+            trailing_trivia.iter().map(to_trivia).collect(),
+        )
+    }
+
+    pub(crate) fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub(crate) fn leading_trivia(&self) -> &[GreenTrivia] {
+        &self.leading_trivia
+    }
+
+    pub(crate) fn trailing_trivia(&self) -> &[GreenTrivia] {
+        &self.trailing_trivia
+    }
+
+    /// Width of the token, including its trivia
+    pub(crate) fn width(&self) -> usize {
+        self.leading_trivia_width() + self.text.len() + self.trailing_trivia_width()
+    }
+
+    pub(crate) fn leading_trivia_width(&self) -> usize {
+        self.leading_trivia.iter().map(GreenTrivia::width).sum()
+    }
+
+    fn trailing_trivia_width(&self) -> usize {
+        self.trailing_trivia.iter().map(GreenTrivia::width).sum()
+    }
+}
+
+impl GreenTrivia {
+    pub(crate) fn new(kind: TokenKind, text: impl ToString) -> Self {
+        Self {
+            kind,
+            text: text.to_string(),
+        }
+    }
+
+    pub(crate) fn kind(&self) -> TokenKind {
+        self.kind
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub(crate) fn width(&self) -> usize {
+        self.text.len()
+    }
+}
+
+pub(crate) struct GreenNodeBuilder {
+    kind: Option<SyntaxKind>,
+    children: Vec<GreenChild>,
+}
+
+impl GreenNodeBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            kind: None,
+            children: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push_node(&mut self, node: GreenNode) {
+        self.children.push(GreenChild::Node(node));
+    }
+
+    pub(crate) fn push_token(&mut self, token: GreenToken) {
+        self.children.push(GreenChild::Token(token));
+    }
+
+    pub(crate) fn kind(&mut self, kind: SyntaxKind) {
+        self.kind = Some(kind)
+    }
+
+    pub(crate) fn build(self) -> GreenNode {
+        GreenNodeData::new(self.kind.unwrap(), self.children)
+    }
+}
+
+/// A node in the red tree
+#[derive(Debug, Clone)]
+pub struct SyntaxNode(Rc<SyntaxNodeData>);
+
+#[derive(Debug)]
+struct SyntaxNodeData {
+    green: GreenNode,
+    /// Absolute offset of the node, including its leading trivia
+    offset: usize,
+    parent: Option<SyntaxNode>,
+    /// Index of the node in its parent's children
+    index: usize,
+}
+
+/// A token in the red tree
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    green: GreenToken,
+    /// Absolute offset of the token, including its leading trivia
+    offset: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxNodeChild {
+    Token(SyntaxToken),
+    Tree(SyntaxNode),
+}
+
+impl SyntaxNode {
+    pub(crate) fn new_root(green: GreenNode) -> Self {
+        Self(Rc::new(SyntaxNodeData {
+            green,
+            offset: 0,
+            parent: None,
+            index: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind()
+    }
+
+    pub(crate) fn green(&self) -> &GreenNode {
+        &self.0.green
+    }
+
+    /// Range of the node, without the leading trivia of its first token
+    /// and the trailing trivia of its last token
+    pub fn range(&self) -> SourceRange {
+        let full_range = self.full_range();
+        let green = &self.0.green;
+        let leading = green
+            .first_token()
+            .map_or(0, |token| token.leading_trivia_width());
+        let trailing = green
+            .last_token()
+            .map_or(0, |token| token.trailing_trivia_width());
+        let start = full_range.start().value() + leading;
+        let end = (full_range.end().value() - trailing).max(start);
+        SourceRange::new(SourceIndex::new(start), SourceIndex::new(end))
+    }
+
+    /// Range of the node, including all of its trivia
+    pub fn full_range(&self) -> SourceRange {
+        SourceRange::new(
+            SourceIndex::new(self.0.offset),
+            SourceIndex::new(self.0.offset + self.0.green.width()),
+        )
+    }
+
+    /// Text of the node, without the trivia around it
+    pub fn text(&self) -> String {
+        let offset = self.0.offset;
+        let range = self.range();
+        self.to_string()[range.start().value() - offset..range.end().value() - offset].to_owned()
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// The root of the tree containing this node
+    pub fn root(&self) -> SyntaxNode {
+        let mut node = self.clone();
+        while let Some(parent) = node.parent() {
+            node = parent;
+        }
+        node
+    }
+
+    /// Index of the node in its parent's children, tokens included
+    pub(crate) fn index(&self) -> usize {
+        self.0.index
+    }
+
+    /// Replace this node with `green` in a copy of its tree.
+    /// Returns the new node, whose ancestors lead to the root of the new tree.
+    pub(crate) fn replace_with(&self, green: GreenNode) -> SyntaxNode {
+        match self.parent() {
+            None => SyntaxNode::new_root(green),
+            Some(parent) => {
+                let parent_green = parent
+                    .green()
+                    .replace_child(self.index(), GreenChild::Node(green));
+                let parent = parent.replace_with(parent_green);
+                parent.children()[self.index()]
+                    .as_node()
+                    .cloned()
+                    .expect("Replaced child must be a node")
+            }
+        }
+    }
+
+    pub fn children(&self) -> Vec<SyntaxNodeChild> {
+        let mut offset = self.0.offset;
+        let mut children = Vec::with_capacity(self.0.green.children().len());
+        for (index, child) in self.0.green.children().iter().enumerate() {
+            children.push(match child {
+                GreenChild::Node(node) => {
+                    SyntaxNodeChild::Tree(SyntaxNode(Rc::new(SyntaxNodeData {
+                        green: node.clone(),
+                        offset,
+                        parent: Some(self.clone()),
+                        index,
+                    })))
+                }
+                GreenChild::Token(token) => SyntaxNodeChild::Token(SyntaxToken {
+                    green: token.clone(),
+                    offset,
+                }),
+            });
+            offset += child.width();
+        }
+        children
+    }
+
+    pub fn first_token(&self) -> Option<SyntaxToken> {
+        self.children().into_iter().find_map(|child| match child {
+            SyntaxNodeChild::Token(token) => Some(token),
+            SyntaxNodeChild::Tree(node) => node.first_token(),
+        })
+    }
+
+    pub fn last_token(&self) -> Option<SyntaxToken> {
+        self.children()
+            .into_iter()
+            .rev()
+            .find_map(|child| match child {
+                SyntaxNodeChild::Token(token) => Some(token),
+                SyntaxNodeChild::Tree(node) => node.last_token(),
+            })
+    }
+
+    /// Walk the subtree rooted at this node in preorder,
+    /// yielding an event when entering and when leaving every node
+    pub fn preorder(&self) -> Preorder {
+        Preorder {
+            start: Some(self.clone()),
+            stack: Vec::new(),
+        }
+    }
+
+    /// This node and all of its descendants, in preorder
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.preorder().filter_map(|event| match event {
+            WalkEvent::Enter(node) => Some(node),
+            WalkEvent::Leave(_) => None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalkEvent<T> {
+    Enter(T),
+    Leave(T),
+}
+
+/// Preorder iterator over the nodes of a subtree, see [`SyntaxNode::preorder`]
+#[derive(Debug)]
+pub struct Preorder {
+    start: Option<SyntaxNode>,
+    /// Nodes being walked, with their children yet to be visited
+    stack: Vec<(SyntaxNode, std::vec::IntoIter<SyntaxNodeChild>)>,
+}
+
+impl Preorder {
+    fn enter(&mut self, node: SyntaxNode) -> WalkEvent<SyntaxNode> {
+        let children = node.children().into_iter();
+        self.stack.push((node.clone(), children));
+        WalkEvent::Enter(node)
+    }
+}
+
+impl Iterator for Preorder {
+    type Item = WalkEvent<SyntaxNode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(start) = self.start.take() {
+            return Some(self.enter(start));
+        }
+        loop {
+            let (_, children) = self.stack.last_mut()?;
+            match children.next() {
+                Some(SyntaxNodeChild::Tree(child)) => return Some(self.enter(child)),
+                Some(SyntaxNodeChild::Token(_)) => continue,
+                None => {
+                    let (node, _) = self.stack.pop().unwrap();
+                    return Some(WalkEvent::Leave(node));
+                }
+            }
+        }
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> TokenKind {
+        self.green.kind()
+    }
+
+    /// Text of the token, without trivia
+    pub fn text(&self) -> &str {
+        self.green.text()
+    }
+
+    /// Range of the token's text, without trivia
+    pub fn range(&self) -> SourceRange {
+        let start = self.offset + self.green.leading_trivia_width();
+        SourceRange::new(
+            SourceIndex::new(start),
+            SourceIndex::new(start + self.green.text().len()),
+        )
+    }
+
+    pub(crate) fn leading_trivia(&self) -> Vec<Token> {
+        trivia_tokens(self.green.leading_trivia(), self.offset)
+    }
+
+    pub(crate) fn trailing_trivia(&self) -> Vec<Token> {
+        trivia_tokens(self.green.trailing_trivia(), self.range().end().value())
+    }
+}
+
+impl SyntaxNodeChild {
+    pub fn as_token(&self) -> Option<&SyntaxToken> {
+        match self {
+            SyntaxNodeChild::Token(token) => Some(token),
+            _ => None,
+        }
+    }
+
+    pub fn as_node(&self) -> Option<&SyntaxNode> {
+        match self {
+            SyntaxNodeChild::Tree(node) => Some(node),
+            _ => None,
+        }
+    }
+}
+
+fn trivia_tokens(trivia: &[GreenTrivia], mut offset: usize) -> Vec<Token> {
+    trivia
+        .iter()
+        .map(|trivia| {
+            let start = offset;
+            offset += trivia.width();
+            Token {
+                kind: trivia.kind(),
+                range: SourceRange::new(SourceIndex::new(start), SourceIndex::new(offset)),
+            }
+        })
+        .collect()
+}
+
+impl Display for GreenNodeData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenChild::Node(node) => write!(f, "{node}")?,
+                GreenChild::Token(token) => write!(f, "{token}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for GreenTokenData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for trivia in &self.leading_trivia {
+            f.write_str(trivia.text())?;
+        }
+        f.write_str(&self.text)?;
+        for trivia in &self.trailing_trivia {
+            f.write_str(trivia.text())?;
+        }
+        Ok(())
+    }
+}
+
+/// Print the node with all of its trivia, which is exactly the source it was parsed from
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.green)
+    }
+}
+
+impl Display for SyntaxToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.green)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyntaxKind {
+    Document,
+    NodeDefinition,
+    Label,
+    RootNodeIdentifier,
+    NonRootNodeIdentifier,
+    NodeName,
+    NodeAddress,
+    NodeBody,
+    NodeBodyEntries,
+    BoolPropertyDefinition,
+    NonBoolPropertyDefinition,
+    PropertyName,
+    PropertyValues,
+    ArrayValue,
+    IntCell,
+    PhandleCell,
+    MacroCell,
+    StringValue,
+}
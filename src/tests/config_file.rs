@@ -0,0 +1,141 @@
+use std::{fs, path::Path};
+
+use insta::assert_snapshot;
+
+use crate::{
+    config_file::{discover_config, parse_config, read_config},
+    formatter::{EndOfLine, IndentStyle},
+    layout::Layout,
+};
+
+#[test]
+fn parse_config_options() {
+    let config_str = r#"
+indent_width = 2
+indent_style = "tab"
+max_width = 80
+bindings_grid = false
+end_of_line = "crlf"
+insert_final_newline = true
+layout = "corne"
+"#;
+    let config = parse_config(config_str, Path::new("")).unwrap();
+    assert_eq!(config.indent_width, 2);
+    assert_eq!(config.indent_style, IndentStyle::Tab);
+    assert_eq!(config.max_width, 80);
+    assert!(!config.bindings_grid);
+    assert!(!config.pad_matrix_transform);
+    assert_eq!(config.end_of_line, EndOfLine::Crlf);
+    assert!(config.insert_final_newline);
+    assert_eq!(config.layout, Layout::preset("corne"));
+}
+
+#[test]
+fn parse_layout_rows() {
+    let config = parse_config(
+        "layout_rows = [[2, 2], [1, 1]]\nsplit_gap = 2\n",
+        Path::new(""),
+    )
+    .unwrap();
+    assert_eq!(
+        config.layout,
+        Some(Layout::from_rows(&[vec![2, 2], vec![1, 1]], 2).unwrap())
+    );
+}
+
+#[test]
+fn reject_unknown_options() {
+    let error = parse_config("max_width = 80\nindent = 2\n", Path::new("")).unwrap_err();
+    assert_snapshot!(error, @r"
+    zmk-styler.toml: TOML parse error at line 2, column 1
+      |
+    2 | indent = 2
+      | ^^^^^^
+    unknown field `indent`, expected one of `root`, `indent_width`, `indent_style`, `max_width`, `bindings_grid`, `pad_matrix_transform`, `end_of_line`, `insert_final_newline`, `layout`, `layout_name`, `layout_rows`, `split_gap`
+    ");
+}
+
+#[test]
+fn reject_invalid_values() {
+    let error = parse_config("max_width = \"wide\"\n", Path::new("")).unwrap_err();
+    assert_snapshot!(error, @r#"
+    zmk-styler.toml: TOML parse error at line 1, column 13
+      |
+    1 | max_width = "wide"
+      |             ^^^^^^
+    invalid type: string "wide", expected u32
+    "#);
+    let error = parse_config("indent_style = \"tabs\"\n", Path::new("")).unwrap_err();
+    assert_snapshot!(error, @r#"
+    zmk-styler.toml: TOML parse error at line 1, column 16
+      |
+    1 | indent_style = "tabs"
+      |                ^^^^^^
+    unknown variant `tabs`, expected `space` or `tab`
+    "#);
+    let error = parse_config("max_width = 0\n", Path::new("")).unwrap_err();
+    assert_snapshot!(error, @r"
+    zmk-styler.toml: `max_width` should be at least 1
+    ");
+    let error = parse_config(
+        "layout = \"corne\"\nlayout_rows = [[6, 6]]\n",
+        Path::new(""),
+    )
+    .unwrap_err();
+    assert_snapshot!(error, @r"
+    zmk-styler.toml: only one of `layout` and `layout_rows` can be set
+    ");
+    let error = parse_config("layout = \"planck\"\n", Path::new("/nonexistent")).unwrap_err();
+    assert_snapshot!(error, @r"
+    zmk-styler.toml: `layout` should be one of glove80, corne, sofle, lily58, kyria, ferris, or a QMK info.json, cannot read `planck`: No such file or directory (os error 2)
+    ");
+    let error = parse_config(
+        "layout = \"glove80\"\nlayout_name = \"LAYOUT\"\n",
+        Path::new(""),
+    )
+    .unwrap_err();
+    assert_snapshot!(error, @r"
+    zmk-styler.toml: `layout_name` only applies to a QMK info.json, not the bundled `glove80` layout
+    ");
+}
+
+#[test]
+fn discover_config_files_of_parent_directories() {
+    let root = std::env::temp_dir().join("zmk-styler-discover-config");
+    let shield = root.join("boards/shields/corne");
+    fs::create_dir_all(&shield).unwrap();
+    fs::write(
+        root.join("zmk-styler.toml"),
+        "max_width = 80\nbindings_grid = false\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("boards/zmk-styler.toml"),
+        "root = true\nindent_width = 2\nmax_width = 120\n",
+    )
+    .unwrap();
+    fs::write(shield.join("zmk-styler.toml"), "layout = \"corne\"\n").unwrap();
+
+    // Closer config files override further ones, up to the root one
+    let config = discover_config(&shield.join("corne.keymap")).unwrap();
+    assert_eq!(config.indent_width, 2);
+    assert_eq!(config.max_width, 120);
+    assert!(config.bindings_grid);
+    assert_eq!(config.layout, Layout::preset("corne"));
+
+    let config = discover_config(&root.join("config.keymap")).unwrap();
+    assert_eq!(config.indent_width, 4);
+    assert_eq!(config.max_width, 80);
+    assert!(!config.bindings_grid);
+
+    // Only the directories of the file are searched, not the ones the path goes through
+    let config = discover_config(&shield.join("../../../config.keymap")).unwrap();
+    assert_eq!(config.indent_width, 4);
+    assert_eq!(config.max_width, 80);
+    assert_eq!(config.layout, None);
+
+    // A given config file is used alone
+    let config = read_config(&shield.join("zmk-styler.toml")).unwrap();
+    assert_eq!(config.max_width, 100);
+    assert_eq!(config.layout, Layout::preset("corne"));
+}
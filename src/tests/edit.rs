@@ -0,0 +1,280 @@
+use insta::assert_snapshot;
+
+use crate::{
+    ast::{
+        ArrayCell, ArrayValue, AstNode, BoolPropertyDefinition, Document, IntCell, MacroCell,
+        NodeBodyEntry, NodeDefinition, NonBoolPropertyDefinition, PhandleCell, PropertyDefinition,
+        PropertyValue, StringValue,
+    },
+    format_document,
+    parser::parse,
+    source::Source,
+};
+
+const KEYMAP: &str = r#"/ {
+    behaviors {
+        // Tap dance on the layer key
+        td: tap_dance {
+            compatible = "zmk,behavior-tap-dance";
+            tapping-term-ms = <200>; // too slow?
+            #binding-cells = <0>;
+        };
+    };
+
+    keymap {
+        compatible = "zmk,keymap";
+
+        /* Base layer */ default_layer {
+            bindings = <&kp Q &kp W &kp E>; // QWE
+        };
+    };
+};
+"#;
+
+fn find_node(doc: &Document, name: &str) -> NodeDefinition {
+    doc.syntax()
+        .descendants()
+        .filter_map(|node| NodeDefinition::cast(&node))
+        .find(|node| {
+            node.identifier()
+                .is_ok_and(|identifier| identifier.syntax().text() == name)
+        })
+        .unwrap()
+}
+
+fn find_property(node: &NodeDefinition, name: &str) -> PropertyDefinition {
+    let entries = node.body().unwrap().entries().unwrap();
+    let properties = entries
+        .entries()
+        .into_iter()
+        .filter_map(|entry| match entry {
+            NodeBodyEntry::Property(property) => Some(property),
+            NodeBodyEntry::Node(_) => None,
+        });
+    let property = properties.into_iter().find(|property| {
+        let property_name = match property {
+            PropertyDefinition::Bool(property) => property.name(),
+            PropertyDefinition::NonBool(property) => property.name(),
+        };
+        property_name.unwrap().syntax().text() == name
+    });
+    property.unwrap()
+}
+
+fn non_bool(property: PropertyDefinition) -> NonBoolPropertyDefinition {
+    match property {
+        PropertyDefinition::NonBool(property) => property,
+        PropertyDefinition::Bool(_) => panic!("Expected a non bool property"),
+    }
+}
+
+#[test]
+fn build_document() {
+    let doc = Document::new([NodeDefinition::root([NodeDefinition::new(
+        "keymap",
+        [
+            NodeBodyEntry::from(NonBoolPropertyDefinition::new(
+                "compatible",
+                [StringValue::new("zmk,keymap")],
+            )),
+            NodeBodyEntry::from(NodeDefinition::new(
+                "default_layer",
+                [NonBoolPropertyDefinition::new(
+                    "bindings",
+                    [ArrayValue::new([
+                        ArrayCell::from(PhandleCell::new("kp")),
+                        ArrayCell::from(MacroCell::new("Q")),
+                        ArrayCell::from(PhandleCell::new("mt")),
+                        ArrayCell::from(MacroCell::new("LS(A)")),
+                        ArrayCell::from(IntCell::new(1)),
+                    ])],
+                )],
+            )),
+            NodeBodyEntry::from(BoolPropertyDefinition::new("hold-trigger-on-release")),
+        ],
+    )])]);
+    // Tokens are kept apart, so the printed tree parses back to the same tree
+    let text = doc.syntax().to_string();
+    assert_snapshot!(text, @r#"
+    / {keymap {compatible = "zmk,keymap";default_layer {bindings = <&kp Q &mt LS(A) 1>;};hold-trigger-on-release;};};
+    "#);
+    let (reparsed, diagnostics) = parse(&Source::new(&text));
+    assert!(diagnostics.is_empty());
+    assert_eq!(reparsed.syntax().green(), doc.syntax().green());
+    assert_snapshot!(format_document(&doc), @r#"
+    / {
+        keymap {
+            compatible = "zmk,keymap";
+            default_layer {
+                bindings = <
+                    &kp Q &mt LS(A) 1
+                >;
+            };
+            hold-trigger-on-release;
+        };
+    };
+    "#);
+}
+
+#[test]
+fn edit_document_preserving_comments() {
+    let source = Source::new(KEYMAP);
+    let (doc, _) = parse(&source);
+
+    // Set tapping-term-ms
+    let tapping_term = non_bool(find_property(
+        &find_node(&doc, "tap_dance"),
+        "tapping-term-ms",
+    ));
+    let doc = tapping_term
+        .with_values([ArrayValue::new([IntCell::new(150)])])
+        .unwrap()
+        .document()
+        .unwrap();
+
+    // Change a binding
+    let bindings = non_bool(find_property(&find_node(&doc, "default_layer"), "bindings"));
+    let PropertyValue::Array(array) = bindings
+        .values()
+        .unwrap()
+        .values()
+        .into_iter()
+        .next()
+        .unwrap()
+    else {
+        unreachable!()
+    };
+    let doc = array
+        .replace_cell(3, MacroCell::new("LS(W)"))
+        .document()
+        .unwrap();
+
+    // Add a layer
+    let layer = NodeDefinition::new(
+        "lower_layer",
+        [NonBoolPropertyDefinition::new(
+            "bindings",
+            [ArrayValue::new([
+                ArrayCell::from(PhandleCell::new("trans")),
+                ArrayCell::from(PhandleCell::new("bt")),
+                ArrayCell::from(MacroCell::new("BT_SEL")),
+                ArrayCell::from(IntCell::new(0)),
+            ])],
+        )],
+    );
+    let doc = find_node(&doc, "keymap")
+        .push_entry(layer)
+        .unwrap()
+        .document()
+        .unwrap();
+
+    // Remove a property
+    let binding_cells = find_property(&find_node(&doc, "tap_dance"), "#binding-cells");
+    let doc = binding_cells.remove().unwrap();
+
+    assert_snapshot!(format_document(&doc), @r#"
+    / {
+        behaviors {
+            // Tap dance on the layer key
+            td: tap_dance {
+                compatible = "zmk,behavior-tap-dance";
+                tapping-term-ms = <150>; // too slow?
+            };
+        };
+        keymap {
+            compatible = "zmk,keymap";
+            /* Base layer */ default_layer {
+                bindings = <
+                    &kp Q  &kp LS(W)    &kp E
+                >; // QWE
+            };
+            lower_layer {
+                bindings = <
+                    &trans &bt BT_SEL 0
+                >;
+            };
+        };
+    };
+    "#);
+}
+
+#[test]
+fn replace_node_keeps_surrounding_trivia() {
+    let source = Source::new("/ {\n    /* lead */ a = <1 2>; // trail\n};");
+    let (doc, _) = parse(&source);
+    let root = find_node(&doc, "/");
+    let old = find_property(&root, "a");
+    let new = PropertyDefinition::NonBool(NonBoolPropertyDefinition::new(
+        "b",
+        [StringValue::new("say \"hi\"")],
+    ));
+    let doc = old.replace_with(new).document().unwrap();
+    assert_eq!(
+        doc.syntax().to_string(),
+        "/ {\n    /* lead */ b = \"say \\\"hi\\\"\"; // trail\n};"
+    );
+}
+
+#[test]
+fn edit_array_cells() {
+    let array = ArrayValue::new([IntCell::new(1), IntCell::new(2)]);
+    let array = array.insert_cell(0, IntCell::new(0));
+    assert_eq!(array.syntax().to_string(), "<0 1 2>");
+    let array = array.remove_cell(1).push_cell(IntCell::new(3));
+    assert_eq!(array.syntax().to_string(), "<0 2 3>");
+    let array = array.replace_cell(0, PhandleCell::new("kp"));
+    assert_eq!(array.syntax().to_string(), "<&kp 2 3>");
+}
+
+#[test]
+fn edit_node_entries() {
+    let node = NodeDefinition::new("node@1", [BoolPropertyDefinition::new("a")]);
+    let node = node
+        .insert_entry(0, NodeDefinition::new("child", Vec::<NodeBodyEntry>::new()))
+        .unwrap()
+        .push_entry(BoolPropertyDefinition::new("b"))
+        .unwrap()
+        .remove_entry(1)
+        .unwrap();
+    assert_eq!(node.syntax().to_string(), "node@1 {child {};b;};");
+    // Nodes built on their own are not part of any document
+    assert!(node.document().is_none());
+    assert!(node.remove().is_none());
+}
+
+#[test]
+fn insert_after_line_comments() {
+    let (doc, _) = parse(&Source::new("/ {\n    a; // note\n};"));
+    let root = find_node(&doc, "/");
+    let root = root.push_entry(BoolPropertyDefinition::new("b")).unwrap();
+    assert_eq!(
+        root.syntax().root().to_string(),
+        "/ {\n    a; // note\nb;\n};"
+    );
+    let (doc, _) = parse(&Source::new("/ { // open\n};"));
+    let root = find_node(&doc, "/");
+    let root = root.push_entry(BoolPropertyDefinition::new("b")).unwrap();
+    assert_eq!(root.syntax().root().to_string(), "/ { // open\nb;\n};");
+
+    let (doc, _) = parse(&Source::new("/ {};// c"));
+    let doc = doc.push_statement(NodeDefinition::new("x", Vec::<NodeBodyEntry>::new()));
+    assert_eq!(doc.syntax().to_string(), "/ {};// c\nx {};");
+    assert_snapshot!(format_document(&doc), @r"
+    / {}; // c
+    x {};
+    ");
+}
+
+#[test]
+fn keep_text_of_documents_with_syntax_errors() {
+    for text in ["/ {", "/ { a = <1 };", "/ { a b; };", "/ { a = \"s\" };"] {
+        let (doc, _) = parse(&Source::new(text));
+        assert_eq!(format_document(&doc), text);
+    }
+}
+
+#[test]
+#[should_panic(expected = "isn't a valid")]
+fn reject_invalid_names() {
+    PhandleCell::new("kp Q");
+}
@@ -0,0 +1,120 @@
+use std::{fs, path::Path};
+
+use crate::{
+    config_file::discover_config,
+    editorconfig::Glob,
+    formatter::{EndOfLine, IndentStyle},
+};
+
+fn matches(glob: &str, path: &str) -> bool {
+    Glob::new(glob, Path::new("/repo")).matches(path)
+}
+
+#[test]
+fn match_globs_without_slash_in_any_directory() {
+    assert!(matches("*", "/repo/config/corne.keymap"));
+    assert!(matches("*.keymap", "/repo/config/corne.keymap"));
+    assert!(matches("corne.keymap", "/repo/corne.keymap"));
+    assert!(!matches("*.keymap", "/repo/config/corne.dtsi"));
+    assert!(!matches("*.keymap", "/other/corne.keymap"));
+}
+
+#[test]
+fn match_globs_with_slash_from_their_directory() {
+    assert!(matches("config/*.keymap", "/repo/config/corne.keymap"));
+    assert!(matches("/config/*.keymap", "/repo/config/corne.keymap"));
+    assert!(!matches(
+        "config/*.keymap",
+        "/repo/boards/config/corne.keymap"
+    ));
+    assert!(!matches("config/*", "/repo/config/shields/corne.keymap"));
+    assert!(matches("config/**", "/repo/config/shields/corne.keymap"));
+    assert!(matches(
+        "**/shields/*.dtsi",
+        "/repo/boards/shields/corne.dtsi"
+    ));
+}
+
+#[test]
+fn match_glob_patterns() {
+    assert!(matches("*.{keymap,dtsi}", "/repo/corne.dtsi"));
+    assert!(matches("*.{keymap,{dts,dtsi}}", "/repo/corne.dts"));
+    assert!(!matches("*.{keymap,dtsi}", "/repo/corne.overlay"));
+    assert!(matches("{single}.keymap", "/repo/{single}.keymap"));
+    assert!(matches("corne_?.keymap", "/repo/corne_1.keymap"));
+    assert!(!matches("corne?keymap", "/repo/corne/keymap"));
+    assert!(matches("corne_[lr].keymap", "/repo/corne_l.keymap"));
+    assert!(matches("corne_[a-c].keymap", "/repo/corne_b.keymap"));
+    assert!(!matches("corne_[!lr].keymap", "/repo/corne_l.keymap"));
+    assert!(matches("layer{1..10}.dtsi", "/repo/layer7.dtsi"));
+    assert!(!matches("layer{1..10}.dtsi", "/repo/layer11.dtsi"));
+    assert!(matches("\\*.keymap", "/repo/*.keymap"));
+    assert!(!matches("\\*.keymap", "/repo/corne.keymap"));
+}
+
+#[test]
+fn use_editorconfig_without_styler_config() {
+    let root = std::env::temp_dir().join("zmk-styler-editorconfig");
+    let config_dir = root.join("config");
+    fs::create_dir_all(&config_dir).unwrap();
+    fs::write(
+        root.join(".editorconfig"),
+        r#"root = true
+
+[*]
+indent_style = space
+indent_size = 2
+end_of_line = crlf
+max_line_length = 80
+
+# Later sections override earlier ones
+[*.{keymap,dtsi}]
+indent_style = tab
+tab_width = 8
+indent_size = tab
+insert_final_newline = true
+"#,
+    )
+    .unwrap();
+    fs::write(
+        config_dir.join(".editorconfig"),
+        "[*.keymap]\nmax_line_length = off\nend_of_line = unset\n",
+    )
+    .unwrap();
+
+    let config = discover_config(&root.join("corne.dtsi")).unwrap();
+    assert_eq!(config.indent_style, IndentStyle::Tab);
+    assert_eq!(config.indent_width, 8);
+    assert_eq!(config.end_of_line, EndOfLine::Crlf);
+    assert_eq!(config.max_width, 80);
+    assert!(config.insert_final_newline);
+
+    // Closer files override further ones
+    let config = discover_config(&config_dir.join("corne.keymap")).unwrap();
+    assert_eq!(config.end_of_line, EndOfLine::Auto);
+    assert_eq!(config.max_width, 100);
+
+    // The `.editorconfig` of a directory the path only goes through doesn't apply
+    let shields = root.join("shields");
+    fs::create_dir_all(shields.join("corne")).unwrap();
+    fs::create_dir_all(shields.join("lily58")).unwrap();
+    fs::write(
+        shields.join("corne/.editorconfig"),
+        "[*]\nend_of_line = lf\n",
+    )
+    .unwrap();
+    let config = discover_config(&shields.join("corne/../lily58/lily58.keymap")).unwrap();
+    assert_eq!(config.end_of_line, EndOfLine::Crlf);
+
+    let config = discover_config(&root.join("README.md")).unwrap();
+    assert_eq!(config.indent_style, IndentStyle::Space);
+    assert_eq!(config.indent_width, 2);
+    assert!(!config.insert_final_newline);
+
+    // A styler config takes precedence over all of them
+    fs::write(config_dir.join("zmk-styler.toml"), "max_width = 120\n").unwrap();
+    let config = discover_config(&config_dir.join("corne.keymap")).unwrap();
+    assert_eq!(config.indent_style, IndentStyle::Space);
+    assert_eq!(config.max_width, 120);
+    fs::remove_file(config_dir.join("zmk-styler.toml")).unwrap();
+}
@@ -0,0 +1,124 @@
+use insta::assert_snapshot;
+
+use crate::{
+    formatter::{divergence, format, instability, missing_comment, Config, FormatError},
+    parser::parse,
+    source::{Source, SourceIndex, SourceRange},
+};
+
+#[test]
+fn find_missing_comment() {
+    let source = Source::new("/ {\n    // kept\n    a; // dropped\n    b; // kept\n};");
+    let formatted = "/ {\n    // kept\n    a;\n    b; // kept\n};";
+    assert_eq!(
+        missing_comment(&source, formatted),
+        Some(SourceRange::new(SourceIndex::new(23), SourceIndex::new(33)))
+    );
+}
+
+#[test]
+fn ignore_indentation_of_block_comments() {
+    let source = Source::new("/ {\n  /* block\n      comment */\n  a;\n};");
+    let formatted = "/ {\n    /* block\n     comment */\n    a;\n};";
+    assert_eq!(missing_comment(&source, formatted), None);
+}
+
+#[test]
+fn no_divergence_after_formatting() {
+    let test_str = r#"/ {
+    // layers
+    keymap { compatible = "zmk,keymap";
+        base { bindings = <&kp A /* a */ &kp B>; };
+    };
+};"#;
+    let source = Source::new(test_str);
+    let (doc, _) = parse(&source);
+    let config = Config {
+        verify: true,
+        ..Config::default()
+    };
+    let formatted = format(doc, &source, &config).unwrap();
+    assert_eq!(divergence(&source, &formatted), None);
+}
+
+#[test]
+fn find_first_divergence() {
+    let source = Source::new("/ {\n    node@1 {};\n};");
+    let divergence = divergence(&source, "/ {\n    node@@1 {};\n};").unwrap();
+    assert_eq!(divergence.position, SourceIndex::new(13));
+    assert_snapshot!(divergence.msg, @r"
+    `1` became `@` (line 2, column 10 of the formatted text)
+    ");
+}
+
+#[test]
+fn find_first_divergence_of_comments() {
+    let source = Source::new("/ {\n    a; // first\n    b; // second\n};");
+    let divergence = divergence(&source, "/ {\n    a; // second\n    b; // first\n};").unwrap();
+    assert_eq!(divergence.position, SourceIndex::new(11));
+    assert_snapshot!(divergence.msg, @r"
+    comment `// first` became `// second` (line 2, column 8 of the formatted text)
+    ");
+}
+
+#[test]
+fn find_first_unstable_line() {
+    let instability = instability("/ {\n    a;\n    b;\n};", "/ {\n    a;\n    b; /* c */\n};");
+    let instability = instability.unwrap();
+    assert_eq!(instability.line, 2);
+    assert_eq!(instability.formatted, "    b;");
+    assert_eq!(instability.reformatted, "    b; /* c */");
+}
+
+#[test]
+fn formatting_comments_again_is_a_no_op() {
+    let test_str = r#"/ {
+    map = <&mt LSHFT // shift
+        B>;
+    values = <1> /* one */
+        // two
+        , <2>;
+    empty {
+        /* nothing */
+    };
+};
+/* end */
+"#;
+    let source = Source::new(test_str);
+    let (doc, _) = parse(&source);
+    let config = Config {
+        check_idempotency: true,
+        ..Config::default()
+    };
+    let formatted = format(doc, &source, &config).unwrap();
+    assert_snapshot!(formatted, @r"
+    / {
+        map = <
+            &mt LSHFT // shift
+            B
+        >;
+        values
+            = <1> /* one */
+            // two
+            , <2>
+        ;
+        empty {
+            /* nothing */
+        };
+    };
+    /* end */
+    ");
+}
+
+#[test]
+fn refuse_documents_with_missing_syntax() {
+    for text in ["/ {", "/ { a = <1 };", "/ { a b; };", "/ { a = \"s\" };"] {
+        let source = Source::new(text);
+        let (doc, _) = parse(&source);
+        assert_eq!(
+            format(doc, &source, &Config::default()),
+            Err(FormatError::MissingSyntax),
+            "{text}"
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use insta::assert_snapshot;
+
+use super::debug_formatted;
+
+#[test]
+fn keep_ignored_entries() {
+    let test_str = r#"/ {
+  // zmk-styler: ignore
+  combos   {   compatible  = "zmk,combos";
+      esc { bindings = <&kp ESC>; key-positions = <0    1>; };
+  };
+  other   {   };
+  /* zmk-styler: ignore */
+  arr   =   <1    2>;   // trailing
+  next    = <3>;
+};"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r#"
+    / {
+        // zmk-styler: ignore
+        combos   {   compatible  = "zmk,combos";
+          esc { bindings = <&kp ESC>; key-positions = <0    1>; };
+      };
+        other {};
+        /* zmk-styler: ignore */ arr   =   <1    2>; // trailing
+        next = <3>;
+    };
+    "#);
+}
+
+#[test]
+fn keep_entries_between_off_and_on() {
+    let test_str = r#"/ {
+    keymap {
+        compatible = "zmk,keymap";
+        // zmk-styler: off
+        //  ╭─────┬─────╮
+        //  │  Q  │  W  │
+        //  ╰─────┴─────╯
+        default_layer { bindings = <
+            &kp Q   &kp W
+        >; };
+        lower_layer    {   bindings = <&kp N1   &kp N2>; };
+        // zmk-styler: on
+        raise_layer    {   bindings = <&kp F1   &kp F2>; };
+    };
+  // zmk-styler: off
+  unclosed   =   <1    2>;
+  until_end_of_node  ;
+};
+// zmk-styler: off
+/   {  off   = < 1 >; } ;"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r#"
+    / {
+        keymap {
+            compatible = "zmk,keymap";
+            // zmk-styler: off
+            //  ╭─────┬─────╮
+            //  │  Q  │  W  │
+            //  ╰─────┴─────╯
+            default_layer { bindings = <
+                &kp Q   &kp W
+            >; };
+            lower_layer    {   bindings = <&kp N1   &kp N2>; };
+            // zmk-styler: on
+            raise_layer {
+                bindings = <
+                    &kp F1 &kp F2
+                >;
+            };
+        };
+        // zmk-styler: off
+        unclosed   =   <1    2>;
+      until_end_of_node  ;
+    };
+    // zmk-styler: off
+    /   {  off   = < 1 >; } ;
+    "#);
+}
@@ -0,0 +1,68 @@
+use crate::{
+    formatter::{format, Config, EndOfLine},
+    parser::parse,
+    source::Source,
+};
+
+fn formatted_with_config(test_str: &str, config: Config) -> String {
+    let source = Source::new(test_str);
+    let (doc, _) = parse(&source);
+    format(doc, &source, &config).unwrap()
+}
+
+#[test]
+fn keep_line_breaks_of_source() {
+    let test_str = "/ {\r\n  // A comment\r\n  /* A\r\n   block */\r\n  node {};\r\n};\r\n";
+    let formatted = formatted_with_config(test_str, Config::default());
+    assert_eq!(
+        formatted,
+        "/ {\r\n    // A comment\r\n    /* A\r\n     block */\r\n    node {};\r\n};"
+    );
+}
+
+#[test]
+fn convert_line_breaks() {
+    let test_str = "/ {\r\n  node {};\r\n};";
+    let config = Config {
+        end_of_line: EndOfLine::Lf,
+        ..Config::default()
+    };
+    assert_eq!(
+        formatted_with_config(test_str, config),
+        "/ {\n    node {};\n};"
+    );
+
+    let test_str = "/ {\n  node {};\n};";
+    let config = Config {
+        end_of_line: EndOfLine::Crlf,
+        ..Config::default()
+    };
+    assert_eq!(
+        formatted_with_config(test_str, config.clone()),
+        "/ {\r\n    node {};\r\n};"
+    );
+
+    // Lines of block comments and of entries kept as written too
+    let test_str =
+        "/ {\n  /* block\n   * comment */\n  a;\n  // zmk-styler: off\n  n {\n    b;\n  };\n};";
+    let formatted = formatted_with_config(test_str, config);
+    assert_eq!(
+        formatted,
+        "/ {\r\n    /* block\r\n     * comment */\r\n    a;\r\n    // zmk-styler: off\r\n    n {\r\n    b;\r\n  };\r\n};"
+    );
+    assert!(!formatted.replace("\r\n", "").contains('\n'));
+}
+
+#[test]
+fn insert_final_newline() {
+    let config = Config {
+        insert_final_newline: true,
+        ..Config::default()
+    };
+    assert_eq!(formatted_with_config("/ {};", config.clone()), "/ {};\n");
+    assert_eq!(
+        formatted_with_config("/ {};\n\n\n", config.clone()),
+        "/ {};\n"
+    );
+    assert_eq!(formatted_with_config("/ {};\r\n\r\n", config), "/ {};\r\n");
+}
@@ -0,0 +1,306 @@
+use insta::assert_snapshot;
+
+use crate::{formatter::Config, layout::Layout};
+
+use super::{debug_formatted, debug_formatted_with_config};
+
+#[test]
+fn format_layer_bindings_as_grid() {
+    let test_str = r#"/ {
+    keymap {
+        compatible = "zmk,keymap";
+        default_layer {
+            bindings = <&kp Q &kp W &kp E
+            &mt LCTRL A &kp S &kp D
+              &mo 1 &kp SPACE>;
+        };
+        lower_layer {
+            bindings = <&kp N1 &kp N2 &kp N3
+            &kp LS(N1) &trans &kp LC(LS(TAB)) &kp N4
+            &trans &trans>;
+        };
+        empty_layer {
+            bindings = <>;
+        };
+    };
+};"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r#"
+    / {
+        keymap {
+            compatible = "zmk,keymap";
+            default_layer {
+                bindings = <
+                    &kp Q       &kp W     &kp E
+                    &mt LCTRL A &kp S     &kp D
+                    &mo 1       &kp SPACE
+                >;
+            };
+            lower_layer {
+                bindings = <
+                    &kp N1      &kp N2    &kp N3
+                    &kp LS(N1)  &trans    &kp LC(LS(TAB)) &kp N4
+                    &trans      &trans
+                >;
+            };
+            empty_layer {
+                bindings = <>;
+            };
+        };
+    };
+    "#);
+}
+
+#[test]
+fn only_format_keymap_layers_as_grid() {
+    let test_str = r#"/ {
+    behaviors {
+        td: tap_dance {
+            bindings = <&kp A
+            &kp B>;
+        };
+    };
+};"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r"
+    / {
+        behaviors {
+            td: tap_dance {
+                bindings = <&kp A &kp B>;
+            };
+        };
+    };
+    ");
+}
+
+#[test]
+fn format_layer_bindings_by_layout() {
+    let test_str = r#"/ {
+    keymap {
+        compatible = "zmk,keymap";
+        default_layer {
+            bindings = <&kp Q &kp W &kp E &kp R &kp T &kp Y
+            &kp A &kp S &kp D &kp F &kp G &kp H &kp J &kp K
+            &mo 1 &kp SPACE &kp RET &kp BSPC>;
+        };
+        lower_layer {
+            bindings = <&kp N1 &kp N2 &kp N3 &kp N4 &kp N5 &kp N6 &kp LS(N1) &trans &trans &trans &trans &trans &trans &kp LC(LS(TAB)) &trans &trans &trans &trans>;
+        };
+        partial_layer {
+            bindings = <&kp A &kp B
+            &kp C>;
+        };
+    };
+};"#;
+    let layout = Layout::from_rows(&[vec![3, 3], vec![4, 4], vec![2, 2]], 1).unwrap();
+    let config = Config {
+        layout: Some(layout),
+        ..Config::default()
+    };
+    let formatted = debug_formatted_with_config(test_str, config);
+    assert_snapshot!(formatted, @r#"
+    / {
+        keymap {
+            compatible = "zmk,keymap";
+            default_layer {
+                bindings = <
+                               &kp Q  &kp W  &kp E            &kp R   &kp T    &kp Y
+                    &kp A      &kp S  &kp D  &kp F            &kp G   &kp H    &kp J  &kp K
+                                      &mo 1  &kp SPACE        &kp RET &kp BSPC
+                >;
+            };
+            lower_layer {
+                bindings = <
+                               &kp N1 &kp N2 &kp N3           &kp N4  &kp N5   &kp N6
+                    &kp LS(N1) &trans &trans &trans           &trans  &trans   &trans &kp LC(LS(TAB))
+                                      &trans &trans           &trans  &trans
+                >;
+            };
+            partial_layer {
+                bindings = <
+                    &kp A      &kp B
+                    &kp C
+                >;
+            };
+        };
+    };
+    "#);
+}
+
+#[test]
+fn format_layer_bindings_by_preset() {
+    let test_str = r#"/ {
+    keymap {
+        compatible = "zmk,keymap";
+        default_layer {
+            bindings = <
+   &kp TAB &kp Q &kp W &kp E &kp R &kp T &kp Y &kp U &kp I &kp O &kp P &kp BSPC
+   &kp LCTRL &kp A &kp S &kp D &kp F &kp G &kp H &kp J &kp K &kp L &kp SEMI &kp SQT
+   &kp LSHFT &kp Z &kp X &kp C &kp V &kp B &kp N &kp M &kp COMMA &kp DOT &kp FSLH &kp ESC
+   &kp LGUI &mo 1 &kp SPACE &kp RET &mo 2 &kp RALT
+            >;
+        };
+    };
+};"#;
+    let config = Config {
+        layout: Layout::preset("corne"),
+        ..Config::default()
+    };
+    let formatted = debug_formatted_with_config(test_str, config);
+    assert_snapshot!(formatted, @r#"
+    / {
+        keymap {
+            compatible = "zmk,keymap";
+            default_layer {
+                bindings = <
+                    &kp TAB   &kp Q &kp W &kp E    &kp R &kp T           &kp Y   &kp U &kp I     &kp O   &kp P    &kp BSPC
+                    &kp LCTRL &kp A &kp S &kp D    &kp F &kp G           &kp H   &kp J &kp K     &kp L   &kp SEMI &kp SQT
+                    &kp LSHFT &kp Z &kp X &kp C    &kp V &kp B           &kp N   &kp M &kp COMMA &kp DOT &kp FSLH &kp ESC
+                                          &kp LGUI &mo 1 &kp SPACE       &kp RET &mo 2 &kp RALT
+                >;
+            };
+        };
+    };
+    "#);
+}
+
+#[test]
+fn format_layer_bindings_by_matrix_transform() {
+    let test_str = r#"/ {
+    transform {
+        compatible = "zmk,matrix-transform";
+        map = <
+            RC(0,0) RC(0,1)         RC(0,3) RC(0,4)
+            RC(1,0) RC(1,1) RC(1,2) RC(1,3) RC(1,4)
+        >;
+    };
+    keymap {
+        compatible = "zmk,keymap";
+        default_layer {
+            bindings = <&kp Q &kp W &kp O &kp P &kp A &kp S &kp SPACE &kp L &kp SEMI>;
+        };
+    };
+};"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r#"
+    / {
+        transform {
+            compatible = "zmk,matrix-transform";
+            map = <
+                RC(0,0) RC(0,1) RC(0,3) RC(0,4)
+                RC(1,0) RC(1,1) RC(1,2) RC(1,3) RC(1,4)
+            >;
+        };
+        keymap {
+            compatible = "zmk,keymap";
+            default_layer {
+                bindings = <
+                    &kp Q &kp W           &kp O &kp P
+                    &kp A &kp S &kp SPACE &kp L &kp SEMI
+                >;
+            };
+        };
+    };
+    "#);
+}
+
+#[test]
+fn keep_comments_in_layer_bindings() {
+    let test_str = r#"/ {
+    keymap {
+        compatible = "zmk,keymap";
+        default_layer {
+            bindings = < // letters
+                // top row
+                &kp Q &kp W /* home */ &kp E // end of top
+                &kp A    &kp /* shift */ S &kp D
+                /* bottom
+                 * row */
+                &kp Z &kp X &kp C
+                // after
+            >;
+        };
+    };
+};"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r#"
+    / {
+        keymap {
+            compatible = "zmk,keymap";
+            default_layer {
+                bindings = < // letters
+                    // top row
+                    &kp Q &kp W /* home */ &kp E // end of top
+                    &kp A &kp /* shift */ S &kp D
+                    /* bottom
+                     * row */
+                    &kp Z &kp X &kp C
+                    // after
+                >;
+            };
+        };
+    };
+    "#);
+}
+
+#[test]
+fn comments_in_bindings_dont_widen_columns() {
+    let test_str = r#"/ {
+    keymap {
+        compatible = "zmk,keymap";
+        l0 {
+            bindings = <&kp Q /* home */ &kp LSHFT>;
+        };
+        l1 {
+            bindings = <&kp Q &kp LSHFT>;
+        };
+    };
+};"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r#"
+    / {
+        keymap {
+            compatible = "zmk,keymap";
+            l0 {
+                bindings = <
+                    &kp Q /* home */ &kp LSHFT
+                >;
+            };
+            l1 {
+                bindings = <
+                    &kp Q &kp LSHFT
+                >;
+            };
+        };
+    };
+    "#);
+}
+
+#[test]
+fn align_comments_spanning_lines_to_their_binding() {
+    let test_str = r#"/ {
+    keymap {
+        compatible = "zmk,keymap";
+        default_layer {
+            bindings = <&kp Q /* home
+   row */ &kp W &kp E
+ &kp A &kp B &kp C>;
+        };
+    };
+};"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r#"
+    / {
+        keymap {
+            compatible = "zmk,keymap";
+            default_layer {
+                bindings = <
+                    &kp Q /* home
+                     row */ &kp W &kp E
+                    &kp A &kp B &kp C
+                >;
+            };
+        };
+    };
+    "#);
+}
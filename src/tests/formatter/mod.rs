@@ -1,22 +1,31 @@
 use crate::{
-    formatter::{format_document, Format, FormatContext, Writer},
+    formatter::{format_document, Config, Format, FormatContext, Writer},
     parser::parse,
     source::Source,
 };
 
+mod check;
+mod directives;
+mod document;
+mod keymap;
 mod node;
 mod property;
+mod range;
 mod trivia;
 
-fn debug_format(test_str: &str) -> Format {
+fn debug_format(test_str: &str, config: &Config) -> Format {
     let source = Source::new(test_str);
-    let (doc, token_source, _) = parse(&source);
-    let mut format_context = FormatContext::new(&source, &token_source);
+    let (doc, _) = parse(&source);
+    let mut format_context = FormatContext::new(&source, config);
     format_document(doc, &mut format_context).unwrap()
 }
 
 fn debug_formatted(test_str: &str) -> String {
-    let document = debug_format(test_str);
-    let mut writer = Writer::default();
+    debug_formatted_with_config(test_str, Config::default())
+}
+
+fn debug_formatted_with_config(test_str: &str, config: Config) -> String {
+    let document = debug_format(test_str, &config);
+    let mut writer = Writer::new(config);
     writer.write(document)
 }
@@ -1,6 +1,8 @@
 use insta::assert_snapshot;
 
-use super::debug_formatted;
+use crate::formatter::{Config, IndentStyle};
+
+use super::{debug_formatted, debug_formatted_with_config};
 
 #[test]
 fn format_node_correctly() {
@@ -28,3 +30,50 @@ fn format_node_correctly() {
     };
     ");
 }
+
+#[test]
+fn indent_with_tabs() {
+    let test_str = r#"/ {
+    /* A
+     * comment */
+    keymap {
+        compatible = "zmk,keymap";
+        default_layer {
+            bindings = <&kp Q &kp W
+            &kp LCTRL &kp S>;
+        };
+    };
+};"#;
+    let config = Config {
+        indent_style: IndentStyle::Tab,
+        ..Config::default()
+    };
+    let formatted = debug_formatted_with_config(test_str, config);
+    assert_eq!(
+        formatted,
+        "/ {
+\t/* A
+\t * comment */
+\tkeymap {
+\t\tcompatible = \"zmk,keymap\";
+\t\tdefault_layer {
+\t\t\tbindings = <
+\t\t\t\t&kp Q     &kp W
+\t\t\t\t&kp LCTRL &kp S
+\t\t\t>;
+\t\t};
+\t};
+};"
+    );
+}
+
+#[test]
+fn format_node_with_address() {
+    let test_str = "/ {kscan0: kscan@0 {};};";
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r"
+    / {
+        kscan0: kscan@0 {};
+    };
+    ");
+}
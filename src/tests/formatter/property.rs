@@ -1,6 +1,8 @@
 use insta::assert_snapshot;
 
-use super::debug_formatted;
+use crate::formatter::Config;
+
+use super::{debug_formatted, debug_formatted_with_config};
 
 #[test]
 fn format_boolean_property() {
@@ -34,3 +36,207 @@ fn format_string_property() {
     };
     "#);
 }
+
+#[test]
+fn format_non_ascii_string_property() {
+    let test_str = "/ {label   =   \"Layer 😀 ü\";};";
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r#"
+    / {
+        label = "Layer 😀 ü";
+    };
+    "#);
+}
+
+#[test]
+fn format_binding_cells() {
+    let test_str = "/ {bindings = <&kp   Q &mt LCTRL A\n&kp LS(N1) RC(0, 1)>;};";
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r"
+    / {
+        bindings = <&kp Q &mt LCTRL A &kp LS(N1) RC(0, 1)>;
+    };
+    ");
+}
+
+#[test]
+fn format_short_value_list_on_one_line() {
+    let test_str = "/ {bindings = <&mo 1>,<&to 1>;};";
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r"
+    / {
+        bindings = <&mo 1>, <&to 1>;
+    };
+    ");
+}
+
+#[test]
+fn wrap_long_value_list() {
+    let test_str = r#"/ {compatible = "vendor,very-long-keyboard-name-left", "vendor,very-long-keyboard-name-right", "zmk,kscan-gpio";
+    bindings = <&macro_press &kp LSHFT>, <&macro_tap &kp Z &kp M &kp K &kp Z &kp M &kp K &kp Z &kp M &kp K &kp Z &kp M &kp K>;};"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r#"
+    / {
+        compatible
+            = "vendor,very-long-keyboard-name-left"
+            , "vendor,very-long-keyboard-name-right"
+            , "zmk,kscan-gpio"
+        ;
+        bindings
+            = <&macro_press &kp LSHFT>
+            , <&macro_tap &kp Z &kp M &kp K &kp Z &kp M &kp K &kp Z &kp M &kp K &kp Z &kp M &kp K>
+        ;
+    };
+    "#);
+}
+
+#[test]
+fn wrap_long_bindings() {
+    let test_str = "/ {bindings = <&kp Q &kp W &kp E &kp R &kp T &kp Y &kp U &kp I &kp O &kp P &mt LCTRL A &kp LS(N1) 1 2>;};";
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r"
+    / {
+        bindings = <
+            &kp Q
+            &kp W
+            &kp E
+            &kp R
+            &kp T
+            &kp Y
+            &kp U
+            &kp I
+            &kp O
+            &kp P
+            &mt LCTRL A
+            &kp LS(N1) 1 2
+        >;
+    };
+    ");
+}
+
+#[test]
+fn fill_long_cell_lists() {
+    let test_str = "/ {matrix = <0x10 0x11 0x12 0x13 0x14 0x15 0x16 0x17 0x18 0x19 0x1a 0x1b 0x1c 0x1d 0x1e 0x1f 0x20 0x21 0x22 0x23 0x24>;};";
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r"
+    / {
+        matrix = <
+            0x10 0x11 0x12 0x13 0x14 0x15 0x16 0x17 0x18 0x19 0x1a 0x1b 0x1c 0x1d 0x1e 0x1f 0x20 0x21
+            0x22 0x23 0x24
+        >;
+    };
+    ");
+}
+
+#[test]
+fn format_matrix_transform_map_as_grid() {
+    let test_str = r#"/ {
+    transform {
+        compatible = "zmk,matrix-transform";
+        map = <
+RC(0,0) RC(0,1) RC(0,4) RC(0,5)
+  RC(1,0) RC(1,1) RC(1,2) RC(1,3) RC(1,4) RC(1,5)
+        RC(2,1) RC(2,12) RC(2,3) RC(2,4)
+        >;
+    };
+    single_line_transform {
+        compatible = "zmk,matrix-transform";
+        map = <RC(0,0) RC(0,1) RC(1,0) RC(1,1)>;
+    };
+    not_a_transform {
+        map = <RC(0,0) RC(0,1)
+        RC(1,0) RC(1,1)>;
+    };
+};"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r#"
+    / {
+        transform {
+            compatible = "zmk,matrix-transform";
+            map = <
+                RC(0,0) RC(0,1)  RC(0,4) RC(0,5)
+                RC(1,0) RC(1,1)  RC(1,2) RC(1,3) RC(1,4) RC(1,5)
+                RC(2,1) RC(2,12) RC(2,3) RC(2,4)
+            >;
+        };
+        single_line_transform {
+            compatible = "zmk,matrix-transform";
+            map = <
+                RC(0,0) RC(0,1)
+                RC(1,0) RC(1,1)
+            >;
+        };
+        not_a_transform {
+            map = <RC(0,0) RC(0,1) RC(1,0) RC(1,1)>;
+        };
+    };
+    "#);
+}
+
+#[test]
+fn pad_matrix_transform_map() {
+    let test_str = r#"/ {
+    transform {
+        compatible = "zmk,matrix-transform";
+        map = <
+RC(0,0) RC(0,1) RC(0,4) RC(0,5)
+  RC(1,0) RC(1,1) RC(1,2) RC(1,3) RC(1,4) RC(1,5)
+        RC(2,1) RC(2,2) RC(2,3) RC(2,4)
+    RC(3,3) RC(3,2)
+        >;
+    };
+};"#;
+    let config = Config {
+        pad_matrix_transform: true,
+        ..Config::default()
+    };
+    let formatted = debug_formatted_with_config(test_str, config);
+    assert_snapshot!(formatted, @r#"
+    / {
+        transform {
+            compatible = "zmk,matrix-transform";
+            map = <
+                RC(0,0) RC(0,1)                 RC(0,4) RC(0,5)
+                RC(1,0) RC(1,1) RC(1,2) RC(1,3) RC(1,4) RC(1,5)
+                        RC(2,1) RC(2,2) RC(2,3) RC(2,4)
+                RC(3,3) RC(3,2)
+            >;
+        };
+    };
+    "#);
+}
+
+#[test]
+fn keep_comments_in_values() {
+    let test_str = r#"/ {
+    bindings
+        = <&macro_press &kp LSHFT>, // press
+          <&macro_tap /* tap */ &kp A>
+        , <&macro_release &kp LSHFT> /* release */
+        ;
+    arr = < // first
+        1 2 /* two */ 3
+        // last
+    >;
+    lines = <1 2 // two
+             3>;
+};"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r"
+    / {
+        bindings
+            = <&macro_press &kp LSHFT> // press
+            , <&macro_tap /* tap */ &kp A>
+            , <&macro_release &kp LSHFT> /* release */
+        ;
+        arr = < // first
+            1 2 /* two */ 3
+            // last
+        >;
+        lines = <
+            1 2 // two
+            3
+        >;
+    };
+    ");
+}
@@ -0,0 +1,150 @@
+use insta::assert_snapshot;
+
+use crate::{
+    formatter::{format_range, Config},
+    parser::parse,
+    source::{Source, SourceIndex, SourceRange},
+};
+
+/// Format the range of the first occurrence of `selected` in `test_str`
+fn formatted_range(test_str: &str, selected: &str) -> String {
+    let start = test_str.find(selected).unwrap();
+    let range = SourceRange::new(
+        SourceIndex::new(start),
+        SourceIndex::new(start + selected.len()),
+    );
+    let source = Source::new(test_str);
+    let (doc, _) = parse(&source);
+    format_range(doc, &source, range, &Config::default()).unwrap()
+}
+
+#[test]
+fn format_property_in_range() {
+    let test_str = r#"/ {
+  keymap   {
+    compatible   =   "zmk,keymap";
+       default_layer   {
+  bindings   =   <&kp A   &kp B>;   // letters
+      label =    "Base";
+    };
+  };
+};
+"#;
+    let formatted = formatted_range(test_str, "&kp B");
+    assert_snapshot!(formatted, @r#"
+    / {
+      keymap   {
+        compatible   =   "zmk,keymap";
+           default_layer   {
+                bindings = <
+                    &kp A &kp B
+                >;   // letters
+          label =    "Base";
+        };
+      };
+    };
+    "#);
+}
+
+#[test]
+fn format_sibling_entries_in_range() {
+    let test_str = r#"/ {
+  keymap   {
+    compatible   =   "zmk,keymap";
+    // first layer
+       default_layer   {   bindings   =   <&kp A>;   };
+    lower_layer {
+    bindings =   <&kp B>; };
+    raise_layer    {   };
+  };
+};
+"#;
+    let formatted = formatted_range(
+        test_str,
+        "default_layer   {   bindings   =   <&kp A>;   };\n    lower",
+    );
+    assert_snapshot!(formatted, @r#"
+    / {
+      keymap   {
+        compatible   =   "zmk,keymap";
+        // first layer
+            default_layer {
+                bindings = <
+                    &kp A
+                >;
+            };
+            lower_layer {
+                bindings = <
+                    &kp B
+                >;
+            };
+        raise_layer    {   };
+      };
+    };
+    "#);
+}
+
+#[test]
+fn format_whole_node_when_range_overlaps_its_header() {
+    let test_str = r#"/ {
+  keymap   {
+    compatible   =   "zmk,keymap";
+    default_layer   {   bindings   =   <&kp A>;   };
+  };
+  other   {   };
+};
+"#;
+    let formatted = formatted_range(test_str, "keymap   {\n    compatible");
+    assert_snapshot!(formatted, @r#"
+    / {
+        keymap {
+            compatible = "zmk,keymap";
+            default_layer {
+                bindings = <
+                    &kp A
+                >;
+            };
+        };
+      other   {   };
+    };
+    "#);
+}
+
+#[test]
+fn keep_source_when_range_overlaps_nothing() {
+    let test_str = r#"// header   comment
+
+/ {
+  keymap   {   compatible   =   "zmk,keymap";   };
+};
+"#;
+    let formatted = formatted_range(test_str, "header");
+    assert_eq!(formatted, test_str);
+}
+
+#[test]
+fn format_range_in_ignored_entries() {
+    let test_str = r#"/ {
+  // zmk-styler: ignore
+  arr   =   <1    2>;
+  next    = <3>;
+};
+"#;
+    let formatted = formatted_range(test_str, "<1");
+    assert_eq!(formatted, test_str);
+}
+
+#[test]
+fn keep_entries_turned_off_around_the_range() {
+    let test_str = r#"/ {
+  // zmk-styler: off
+  kept   {
+    q=<7>;
+  };
+  // zmk-styler: on
+  next    = <3>;
+};
+"#;
+    let formatted = formatted_range(test_str, "q=<7>;");
+    assert_eq!(formatted, test_str);
+}
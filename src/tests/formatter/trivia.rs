@@ -91,3 +91,84 @@ fn format_block_comments() {
     };
     "#);
 }
+
+#[test]
+fn trailing_comment_does_not_break_code() {
+    let test_str = "/ {bindings = <&kp Q &kp W &kp E &kp R &kp T>; // A trailing comment long enough to go past the maximum width\n};";
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r"
+    / {
+        bindings = <&kp Q &kp W &kp E &kp R &kp T>; // A trailing comment long enough to go past the maximum width
+    };
+    ");
+}
+
+#[test]
+fn format_comments_around_boolean_properties() {
+    let test_str = "/ {
+        // hold-trigger-on-release
+        hold-trigger-on-release; // after
+        flag /* before semicolon */ ;
+    };";
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r"
+    / {
+        // hold-trigger-on-release
+        hold-trigger-on-release; // after
+        flag /* before semicolon */;
+    };
+    ");
+}
+
+#[test]
+fn format_comments_around_every_token() {
+    let test_str = r#"// top
+/ /* root */ { // open
+    lbl: /* after label */ node /* before address */ @1 /* before curly */ { // in curly
+        prop /* before equal */ = /* after equal */ "s" /* after value */ , /* after comma */ "t";
+        other =
+            // before value
+            <1>;
+    } /* after curly */ ; // after node
+};
+// end of file
+"#;
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r#"
+    // top
+    / /* root */ { // open
+        lbl: /* after label */ node /* before address */@1 /* before curly */ { // in curly
+            prop /* before equal */ = /* after equal */ "s" /* after value */ /* after comma */, "t";
+            other =
+                // before value
+                <1>;
+        } /* after curly */; // after node
+    };
+    // end of file
+    "#);
+}
+
+#[test]
+fn format_comments_in_empty_bodies() {
+    let test_str = "/ {
+    a {  /* x */ };
+    b { // y
+    };
+    c {
+        /* z */
+    };
+    arr = <  /* w */ >;
+};";
+    let formatted = debug_formatted(test_str);
+    assert_snapshot!(formatted, @r"
+    / {
+        a { /* x */ };
+        b { // y
+        };
+        c {
+            /* z */
+        };
+        arr = < /* w */ >;
+    };
+    ");
+}
@@ -0,0 +1,154 @@
+//! Random valid documents, with comments and line breaks between their tokens,
+//! must format to text that formatting doesn't change.
+
+use proptest::{collection::vec, prelude::*};
+
+use crate::{
+    formatter::{format, Config},
+    parser::parse,
+    source::Source,
+};
+
+fn format_text(text: &str) -> String {
+    let source = Source::new(text);
+    let (doc, diagnostics) = parse(&source);
+    assert_eq!(diagnostics, [], "Invalid document:\n{text}");
+    let config = Config {
+        verify: true,
+        ..Config::default()
+    };
+    format(doc, &source, &config)
+        .unwrap_or_else(|error| panic!("{error:?} formatting:\n{text}"))
+}
+
+fn cell() -> impl Strategy<Value = Vec<String>> {
+    prop_oneof![
+        Just(vec!["&kp".to_owned(), "A".to_owned()]),
+        Just(vec!["&mt".to_owned(), "LSHFT".to_owned(), "B".to_owned()]),
+        Just(vec!["&trans".to_owned()]),
+        Just(vec!["&kp".to_owned(), "LS(A)".to_owned()]),
+        Just(vec!["RC(0,1)".to_owned()]),
+        Just(vec!["RC(1,0)".to_owned()]),
+        Just(vec!["42".to_owned()]),
+        Just(vec!["0x10".to_owned()]),
+    ]
+}
+
+fn value() -> impl Strategy<Value = Vec<String>> {
+    prop_oneof![
+        3 => vec(cell(), 0..14).prop_map(|cells| {
+            let mut atoms = vec!["<".to_owned()];
+            atoms.extend(cells.into_iter().flatten());
+            atoms.push(">".to_owned());
+            atoms
+        }),
+        1 => prop_oneof![
+            Just(r#""zmk,keymap""#),
+            Just(r#""zmk,matrix-transform""#),
+            Just(r#""Base""#),
+        ]
+        .prop_map(|string| vec![string.to_owned()]),
+    ]
+}
+
+fn property() -> impl Strategy<Value = Vec<String>> {
+    let bool_property = prop_oneof![Just("flag"), Just("hold-trigger-on-release")]
+        .prop_map(|name| vec![name.to_owned(), ";".to_owned()]);
+    let property = (
+        prop_oneof![
+            Just("bindings"),
+            Just("map"),
+            Just("compatible"),
+            Just("#binding-cells"),
+            Just("display-name"),
+        ],
+        vec(value(), 1..4),
+    )
+        .prop_map(|(name, values)| {
+            let mut atoms = vec![name.to_owned(), "=".to_owned()];
+            for (index, value) in values.into_iter().enumerate() {
+                if index > 0 {
+                    atoms.push(",".to_owned());
+                }
+                atoms.extend(value);
+            }
+            atoms.push(";".to_owned());
+            atoms
+        });
+    prop_oneof![1 => bool_property, 3 => property]
+}
+
+fn node(entries: impl Strategy<Value = Vec<Vec<String>>>) -> impl Strategy<Value = Vec<String>> {
+    (
+        prop::option::of(prop_oneof![Just("lbl"), Just("default_layer")]),
+        prop_oneof![Just("keymap"), Just("layer"), Just("kscan@0"), Just("transform")],
+        entries,
+    )
+        .prop_map(|(label, name, entries)| {
+            let mut atoms = Vec::new();
+            if let Some(label) = label {
+                atoms.extend([label.to_owned(), ":".to_owned()]);
+            }
+            atoms.extend([name.to_owned(), "{".to_owned()]);
+            atoms.extend(entries.into_iter().flatten());
+            atoms.extend(["}".to_owned(), ";".to_owned()]);
+            atoms
+        })
+}
+
+fn entry() -> impl Strategy<Value = Vec<String>> {
+    property().prop_recursive(3, 32, 5, |entry| {
+        prop_oneof![2 => property(), 1 => node(vec(entry, 0..5))]
+    })
+}
+
+/// Comments and line breaks, some of them telling the formatter to keep entries as they are
+fn trivia() -> impl Strategy<Value = &'static str> {
+    prop_oneof![
+        12 => Just(" "),
+        6 => Just("\n"),
+        1 => Just("\n\n"),
+        1 => Just("\t"),
+        1 => Just(" /* block */ "),
+        1 => Just("\n/* multi\n   line */\n"),
+        1 => Just(" // line\n"),
+        1 => Just("\n// own line\n"),
+        1 => Just("\n// zmk-styler: ignore\n"),
+        1 => Just("\n// zmk-styler: off\n"),
+        1 => Just("\n/* zmk-styler: on */\n"),
+    ]
+}
+
+fn document() -> impl Strategy<Value = String> {
+    let root = vec(entry(), 0..6).prop_map(|entries| {
+        let mut atoms = vec!["/".to_owned(), "{".to_owned()];
+        atoms.extend(entries.into_iter().flatten());
+        atoms.extend(["}".to_owned(), ";".to_owned()]);
+        atoms
+    });
+    let statement = prop_oneof![3 => root, 1 => node(vec(entry(), 0..4))];
+    vec(statement, 1..3)
+        .prop_map(|statements| statements.into_iter().flatten().collect::<Vec<_>>())
+        .prop_flat_map(|atoms| {
+            let trivia = vec(trivia(), atoms.len() + 1);
+            (Just(atoms), trivia)
+        })
+        .prop_map(|(atoms, trivia)| {
+            let mut text = String::new();
+            for (atom, trivia) in atoms.iter().zip(&trivia) {
+                text.push_str(atom);
+                text.push_str(trivia);
+            }
+            text.push_str(trivia.last().unwrap());
+            text
+        })
+}
+
+proptest! {
+    #[test]
+    fn formatting_is_idempotent(text in document()) {
+        let formatted = format_text(&text);
+        let reformatted = format_text(&formatted);
+        prop_assert_eq!(formatted, reformatted, "Formatting:\n{}", text);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{
+    layout::{KeyPosition, Layout},
+    parser::parse,
+    source::Source,
+};
+
+fn positions(layout: &Layout) -> Vec<(usize, usize)> {
+    layout
+        .keys()
+        .iter()
+        .map(|KeyPosition { row, column }| (*row, *column))
+        .collect()
+}
+
+#[test]
+fn layout_from_rows() {
+    let layout = Layout::from_rows(&[vec![2, 2], vec![3, 3], vec![1, 1], vec![4]], 1).unwrap();
+    assert_eq!(
+        positions(&layout),
+        [
+            (0, 1),
+            (0, 2),
+            (0, 4),
+            (0, 5),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (1, 4),
+            (1, 5),
+            (1, 6),
+            (2, 2),
+            (2, 4),
+            (3, 0),
+            (3, 1),
+            (3, 2),
+            (3, 3),
+        ]
+    );
+}
+
+#[test]
+fn reject_rows_with_more_than_two_halves() {
+    let error = Layout::from_rows(&[vec![2, 2], vec![1, 2, 3]], 1).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "row 2 should list the keys of the whole row, or of its two halves, found 3 numbers"
+    );
+}
+
+const QMK_INFO: &str = r#"{
+    "keyboard_name": "split",
+    "layouts": {
+        "LAYOUT": {
+            "layout": [
+                {"matrix": [0, 0], "x": 0, "y": 0.25},
+                {"matrix": [0, 1], "x": 1, "y": 0},
+                {"matrix": [0, 2], "x": 3.5, "y": 0},
+                {"matrix": [0, 3], "x": 4.5, "y": 0.25},
+                {"matrix": [1, 0], "x": 1.5, "y": 1.5, "r": 15},
+                {"matrix": [1, 1], "x": 1.75, "y": 1.75},
+                {"matrix": [1, 2], "x": 3, "y": 1.5}
+            ]
+        },
+        "LAYOUT_other": {"layout": []}
+    }
+}"#;
+
+#[test]
+fn layout_from_qmk_info() {
+    let layout = Layout::from_qmk_info(QMK_INFO, Some("LAYOUT")).unwrap();
+    assert_eq!(
+        positions(&layout),
+        [(0, 0), (0, 1), (0, 4), (0, 5), (1, 2), (1, 3), (1, 4)]
+    );
+}
+
+#[test]
+fn reject_unknown_qmk_layouts() {
+    let error = Layout::from_qmk_info(QMK_INFO, Some("LAYOUT_split")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "no layout `LAYOUT_split` in QMK info.json"
+    );
+    let error = Layout::from_qmk_info(QMK_INFO, None).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "QMK info.json should have exactly one layout, or the layout to use should be named, found: LAYOUT, LAYOUT_other"
+    );
+    let error = Layout::from_qmk_info("{}", None).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid QMK info.json: missing field `layouts` at line 1 column 2"
+    );
+}
+
+#[test]
+fn bundled_presets() {
+    let key_counts = Layout::preset_names()
+        .map(|name| (name, Layout::preset(name).unwrap().keys().len()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        key_counts,
+        [
+            ("glove80", 80),
+            ("corne", 42),
+            ("sofle", 60),
+            ("lily58", 58),
+            ("kyria", 50),
+            ("ferris", 34),
+        ]
+    );
+    assert_eq!(Layout::preset("planck"), None);
+}
+
+#[test]
+fn layout_from_matrix_transform() {
+    let test_str = r#"/ {
+    chosen {
+        zmk,matrix-transform = &split_transform;
+    };
+    default_transform: keymap_transform_0 {
+        compatible = "zmk,matrix-transform";
+        map = <RC(0,0) RC(0,1) RC(1,0)>;
+    };
+    split_transform: keymap_transform_1 {
+        compatible = "zmk,matrix-transform";
+        map = <
+            RC(0,0) RC(0,1)         RC(0,4) RC(0,5)
+            RC(1,0) RC(1,1) RC(1,2) RC(1,3) RC(1,4) RC(1,5)
+                    RC(2,2) RC(2,1) RC(2,3)
+        >;
+    };
+};"#;
+    let source = Source::new(test_str);
+    let (document, _) = parse(&source);
+    let layout = Layout::from_matrix_transform(&document).unwrap();
+    assert_eq!(
+        positions(&layout),
+        [
+            (0, 0),
+            (0, 1),
+            (0, 4),
+            (0, 5),
+            (1, 0),
+            (1, 1),
+            (1, 2),
+            (1, 3),
+            (1, 4),
+            (1, 5),
+            (2, 0),
+            (2, 1),
+            (2, 2),
+        ]
+    );
+}
+
+#[test]
+fn layout_from_single_line_matrix_transform() {
+    let test_str = r#"/ {
+    transform {
+        compatible = "zmk,matrix-transform";
+        map = <RC(0,0) RC(0,2) RC(1,1) RC(1,2)>;
+    };
+};"#;
+    let source = Source::new(test_str);
+    let (document, _) = parse(&source);
+    let layout = Layout::from_matrix_transform(&document).unwrap();
+    assert_eq!(positions(&layout), [(0, 0), (0, 2), (1, 1), (1, 2)]);
+}
@@ -52,7 +52,19 @@ mod test {
             Token(STRING, [58..75], "\"LAYER_TAP_DANCE\"")
             Token(SEMICOLON, [75..76], ";")
         ]
-        "#)
+        "#);
+
+        let tokens = lex(r#"label = "a \"quoted\" \\";"#);
+        assert_snapshot!(tokens, @r#"
+        [
+            Token(NAME, [0..5], "label")
+            Token(SPACE, [5..6], " ")
+            Token(EQUAL, [6..7], "=")
+            Token(SPACE, [7..8], " ")
+            Token(STRING, [8..25], "\"a \\\"quoted\\\" \\\\\"")
+            Token(SEMICOLON, [25..26], ";")
+        ]
+        "#);
     }
 
     #[test]
@@ -179,6 +191,62 @@ mod test {
         "#);
     }
 
+    #[test]
+    fn lex_comment_before_crlf() {
+        let tokens = lex("// single comment\r\n");
+        assert_snapshot!(tokens, @r#"
+        [
+            Token(S_COMMENT, [0..17], "// single comment")
+            Token(NEW_LINE, [17..19], "\r\n")
+        ]
+        "#);
+    }
+
+    #[test]
+    fn lex_non_ascii() {
+        let tokens = lex_with_diagnostics("\u{feff}label = \"é😀\"; // ü\n é\r\"ß");
+        assert_snapshot!(tokens, @r#"
+        [
+            Token(UNKNOWN, [0..3], "\u{feff}")
+            Token(NAME, [3..8], "label")
+            Token(SPACE, [8..9], " ")
+            Token(EQUAL, [9..10], "=")
+            Token(SPACE, [10..11], " ")
+            Token(STRING, [11..19], "\"é😀\"")
+            Token(SEMICOLON, [19..20], ";")
+            Token(SPACE, [20..21], " ")
+            Token(S_COMMENT, [21..26], "// ü")
+            Token(NEW_LINE, [26..27], "\n")
+            Token(SPACE, [27..28], " ")
+            Token(UNKNOWN, [28..30], "é")
+            Token(UNKNOWN, [30..31], "\r")
+            Token(UNKNOWN, [31..34], "\"ß")
+        ]
+        Byte order mark is not supported [0..3]
+        Unexpected character 'é' [28..30]
+        Unexpected character '\r' [30..31]
+        Unterminated string [31..34]
+        "#);
+    }
+
+    fn lex_with_diagnostics(source: &str) -> String {
+        let source = Source::new(source);
+        let mut lexer = Lexer::new(&source);
+        let mut tokens = Vec::new();
+        loop {
+            let token = lexer.next_token();
+            if token.is_eof() {
+                break;
+            }
+            tokens.push(token);
+        }
+        let mut output = print_tokens(&tokens, &source);
+        for diagnostic in lexer.finish() {
+            output.push_str(&format!("\n{} {}", diagnostic.msg, diagnostic.range));
+        }
+        output
+    }
+
     fn lex(source: &str) -> String {
         let source = Source::new(source);
         let mut lexer = Lexer::new(&source);
@@ -1,3 +1,11 @@
+mod config_file;
+mod edit;
+mod editorconfig;
 mod formatter;
+mod idempotency;
+mod layout;
 mod lexer;
 mod parser;
+mod query;
+mod syntax;
+mod visitor;
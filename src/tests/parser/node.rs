@@ -19,6 +19,7 @@ fn parse_node_correctly() {
                 SEMICOLON@[7..8](;)
             )
         )
+        EOF@[8..8]()
     )
     ");
 }
@@ -40,6 +41,7 @@ fn parse_root_node_correctly() {
                 SEMICOLON@[4..5](;)
             )
         )
+        EOF@[5..5]()
     )
     "
     );
@@ -68,6 +70,7 @@ fn parse_node_with_label_correctly() {
                 SEMICOLON@[14..15](;)
             )
         )
+        EOF@[15..15]()
     )
     "
     );
@@ -100,6 +103,7 @@ fn parse_node_with_address_correctly() {
                 SEMICOLON@[17..18](;)
             )
         )
+        EOF@[18..18]()
     )
     "
     );
@@ -156,6 +160,7 @@ fn parse_node_with_children_correctly() {
                 SEMICOLON@[38..39](;)
             )
         )
+        EOF@[39..39]()
     )
     "
     );
@@ -244,6 +249,7 @@ fn parse_node_with_nested_children_correctly() {
                 SEMICOLON@[97..98](;)
             )
         )
+        EOF@[98..98]()
     )
     "
     );
@@ -312,6 +318,7 @@ fn parse_node_with_properties_correctly() {
                 SEMICOLON@[84..85](;)
             )
         )
+        EOF@[85..85]()
     )
     "#
     );
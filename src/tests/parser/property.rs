@@ -26,6 +26,7 @@ fn parse_boolean_property_correctly() {
                 SEMICOLON@[28..29](;)
             )
         )
+        EOF@[29..29]()
     )
     "
     )
@@ -74,6 +75,7 @@ fn parse_i32_array_property_correctly() {
                 SEMICOLON@[25..26](;)
             )
         )
+        EOF@[26..26]()
     )
     "
     )
@@ -109,6 +111,7 @@ fn parse_string_property_correctly() {
                 SEMICOLON@[42..43](;)
             )
         )
+        EOF@[43..43]()
     )
     "#
     )
@@ -144,7 +147,159 @@ fn parse_ill_formed_string_property() {
                 )
             )
         )
+        EOF@[28..28]()
     )
     "#
     )
 }
+
+#[test]
+fn parse_non_ascii_characters() {
+    assert_snapshot!(
+        debug_ast("/ {label = \"Éclair ☕\"; ☕};"),
+        @r#"
+    error: Unexpected character '☕'
+      |
+    1 | / {label = "Éclair ☕"; ☕};
+      |                         ^^ Unexpected character '☕'
+      |
+
+    Document@[0..31](
+        NodeDefinition@[0..31](
+            RootNodeIdentifier@[0..1](
+                ROOT@[0..1](/)
+            )
+            NodeBody@[2..31](
+                L_CURLY@[2..3]({)
+                NodeBodyEntries@[3..29](
+                    NonBoolPropertyDefinition@[3..25](
+                        PropertyName@[3..8](
+                            NAME@[3..8](label)
+                        )
+                        EQUAL@[9..10](=)
+                        PropertyValues@[11..25](
+                            StringValue@[11..24](
+                                STRING@[11..24]("Éclair ☕")
+                            )
+                            SEMICOLON@[24..25](;)
+                        )
+                    )
+                    UNKNOWN@[26..29](☕)
+                )
+                R_CURLY@[29..30](})
+                SEMICOLON@[30..31](;)
+            )
+        )
+        EOF@[31..31]()
+    )
+    "#
+    )
+}
+
+#[test]
+fn parse_binding_cells() {
+    assert_snapshot!(debug_ast("/ {bindings = <&kp Q &mt LCTRL A RC(0,1)>;};"), @r"
+    Document@[0..44](
+        NodeDefinition@[0..44](
+            RootNodeIdentifier@[0..1](
+                ROOT@[0..1](/)
+            )
+            NodeBody@[2..44](
+                L_CURLY@[2..3]({)
+                NodeBodyEntries@[3..42](
+                    NonBoolPropertyDefinition@[3..42](
+                        PropertyName@[3..11](
+                            NAME@[3..11](bindings)
+                        )
+                        EQUAL@[12..13](=)
+                        PropertyValues@[14..42](
+                            ArrayValue@[14..41](
+                                L_ANGLE@[14..15](<)
+                                PhandleCell@[15..18](
+                                    AMP@[15..16](&)
+                                    NAME@[16..18](kp)
+                                )
+                                MacroCell@[19..20](
+                                    NAME@[19..20](Q)
+                                )
+                                PhandleCell@[21..24](
+                                    AMP@[21..22](&)
+                                    NAME@[22..24](mt)
+                                )
+                                MacroCell@[25..30](
+                                    NAME@[25..30](LCTRL)
+                                )
+                                MacroCell@[31..32](
+                                    NAME@[31..32](A)
+                                )
+                                MacroCell@[33..40](
+                                    NAME@[33..35](RC)
+                                    L_PAREN@[35..36](()
+                                    INT@[36..37](0)
+                                    COMMA@[37..38](,)
+                                    INT@[38..39](1)
+                                    R_PAREN@[39..40]())
+                                )
+                                R_ANGLE@[40..41](>)
+                            )
+                            SEMICOLON@[41..42](;)
+                        )
+                    )
+                )
+                R_CURLY@[42..43](})
+                SEMICOLON@[43..44](;)
+            )
+        )
+        EOF@[44..44]()
+    )
+    ");
+}
+
+#[test]
+fn parse_unbalanced_macro_arguments() {
+    assert_snapshot!(debug_ast("/ {map = <RC(0,(1)>;};"), @r"
+    error: Expected R_PAREN, but found R_ANGLE
+      |
+    1 | / {map = <RC(0,(1)>;};
+      |                   ^ Expected R_PAREN, but found R_ANGLE
+      |
+
+    Document@[0..22](
+        NodeDefinition@[0..22](
+            RootNodeIdentifier@[0..1](
+                ROOT@[0..1](/)
+            )
+            NodeBody@[2..22](
+                L_CURLY@[2..3]({)
+                NodeBodyEntries@[3..20](
+                    NonBoolPropertyDefinition@[3..20](
+                        PropertyName@[3..6](
+                            NAME@[3..6](map)
+                        )
+                        EQUAL@[7..8](=)
+                        PropertyValues@[9..20](
+                            ArrayValue@[9..19](
+                                L_ANGLE@[9..10](<)
+                                MacroCell@[10..18](
+                                    NAME@[10..12](RC)
+                                    L_PAREN@[12..13](()
+                                    INT@[13..14](0)
+                                    COMMA@[14..15](,)
+                                    L_PAREN@[15..16](()
+                                    INT@[16..17](1)
+                                    R_PAREN@[17..18]())
+                                )
+                                R_ANGLE@[18..19](>)
+                            )
+                            SEMICOLON@[19..20](;)
+                        )
+                    )
+                )
+                R_CURLY@[20..21](})
+                SEMICOLON@[21..22](;)
+            )
+        )
+        EOF@[22..22]()
+    )
+    ");
+}
@@ -0,0 +1,128 @@
+use std::rc::Rc;
+
+use crate::{
+    ast::{AstNode, Document},
+    parser::{parse, reparse, reparse_incrementally, ParseError, TextEdit},
+    source::{Source, SourceIndex, SourceRange},
+    syntax::{GreenChild, GreenNode},
+};
+
+const KEYMAP: &str = include_str!("../../../tests/glove80.keymap");
+
+#[test]
+fn reparse_edit_inside_node_body_incrementally() {
+    let test_str = r#"/ {
+    behaviors {
+        lower: lower {
+            tapping-term-ms = <200>;
+        };
+    };
+    keymap {
+        compatible = "zmk,keymap";
+    };
+};
+"#;
+    let source = Source::new(test_str);
+    let (doc, _) = parse(&source);
+    let edit = replace(test_str, "200", "250");
+
+    let (new_doc, reparsed_range) = reparse_incrementally(&doc, &edit).unwrap();
+
+    let lower_body_start = test_str.find("{\n            tapping").unwrap();
+    assert_eq!(reparsed_range.start().value(), lower_body_start);
+    assert_same_as_full_reparse(&doc, &[], &edit);
+    // The untouched `keymap` node is shared between the two trees
+    let keymap = |doc: &Document| root_body_entries(doc)[1].clone();
+    assert!(Rc::ptr_eq(&keymap(&doc), &keymap(&new_doc)));
+}
+
+#[test]
+fn reparse_fully_when_edit_is_not_inside_a_node_body() {
+    let source = Source::new(KEYMAP);
+    let (doc, _) = parse(&source);
+    for (from, to) in [
+        ("lower {", "lower "),
+        ("};\n    };", "};\n    }"),
+        ("/* Glove80 system", "/* Glove80"),
+        ("/ {", "/ {}; / {"),
+    ] {
+        let edit = replace(KEYMAP, from, to);
+        assert!(reparse_incrementally(&doc, &edit).is_none(), "{from:?}");
+        assert_same_as_full_reparse(&doc, &[], &edit);
+    }
+}
+
+#[test]
+#[should_panic(expected = "is outside of the document")]
+fn reject_edits_outside_of_the_document() {
+    let test_str = "a {};";
+    let (doc, diagnostics) = parse(&Source::new(test_str));
+    let range = SourceRange::new(SourceIndex::new(3), SourceIndex::new(6));
+    reparse(&doc, &diagnostics, &TextEdit::new(range, ""));
+}
+
+#[test]
+fn reparse_keeps_diagnostics_outside_of_reparsed_node_body() {
+    let test_str = "a {\n    b = <1>;\n};\nc {\n    d = ;\n};\ne {";
+    let source = Source::new(test_str);
+    let (doc, diagnostics) = parse(&source);
+    assert!(!diagnostics.is_empty());
+    let edit = replace(test_str, "<1>", "<1 2 3>");
+    assert!(reparse_incrementally(&doc, &edit).is_some());
+    assert_same_as_full_reparse(&doc, &diagnostics, &edit);
+}
+
+#[test]
+fn reparse_is_identical_to_full_reparse() {
+    let source = Source::new(KEYMAP);
+    let (doc, diagnostics) = parse(&source);
+    let replacements = ["", " ", "\n", "x", ";", "}", "{", "<", "\"", "/*", "// c\n"];
+    for start in 0..KEYMAP.len() {
+        for end in [start, start + 1] {
+            for replacement in replacements {
+                let range = SourceRange::new(SourceIndex::new(start), SourceIndex::new(end));
+                let edit = TextEdit::new(range, replacement);
+                assert_same_as_full_reparse(&doc, &diagnostics, &edit);
+            }
+        }
+    }
+}
+
+fn assert_same_as_full_reparse(doc: &Document, diagnostics: &[ParseError], edit: &TextEdit) {
+    let (new_doc, new_diagnostics) = reparse(doc, diagnostics, edit);
+    let edited = edit.apply(&doc.syntax().to_string());
+    let (expected_doc, expected_diagnostics) = parse(&Source::new(&edited));
+    assert_eq!(
+        new_doc.syntax().green(),
+        expected_doc.syntax().green(),
+        "{edit:?}"
+    );
+    assert_eq!(new_diagnostics, expected_diagnostics, "{edit:?}");
+}
+
+fn replace(text: &str, from: &str, to: &str) -> TextEdit {
+    let start = text.find(from).unwrap();
+    let range = SourceRange::new(
+        SourceIndex::new(start),
+        SourceIndex::new(start + from.len()),
+    );
+    TextEdit::new(range, to)
+}
+
+fn root_body_entries(doc: &Document) -> Vec<GreenNode> {
+    let mut node = doc.syntax().green().clone();
+    // Document -> NodeDefinition -> NodeBody -> NodeBodyEntries
+    for index in [0, 1, 1] {
+        let GreenChild::Node(child) = &node.children()[index] else {
+            panic!("Expected a node");
+        };
+        node = child.clone();
+    }
+    node.children()
+        .iter()
+        .filter_map(|child| match child {
+            GreenChild::Node(node) => Some(node.clone()),
+            GreenChild::Token(_) => None,
+        })
+        .collect()
+}
@@ -0,0 +1,137 @@
+use insta::assert_snapshot;
+
+use crate::{
+    parser::parse,
+    query::{Query, QueryMatch},
+    source::Source,
+};
+
+const KEYMAP: &str = r#"/ {
+    behaviors {
+        hm: homerow_mods {
+            compatible = "zmk,behavior-hold-tap";
+            #binding-cells = <2>;
+            hold-trigger-on-release;
+        };
+        td: tap_dance {
+            compatible = "zmk,behavior-tap-dance";
+            #binding-cells = < 0 >;
+        };
+    };
+
+    keymap {
+        compatible = "zmk,keymap";
+        default_layer {
+            bindings = <&kp Q &hm LSHFT A>;
+        };
+        lower_layer@1 {
+            bindings = <&trans>;
+            label = "say \"lower\"";
+        };
+    };
+};
+"#;
+
+fn debug_query(selector: &str) -> String {
+    let source = Source::new(KEYMAP);
+    let (doc, _) = parse(&source);
+    let query = Query::new(selector).unwrap();
+    query
+        .matches(&doc)
+        .iter()
+        .map(|query_match| {
+            let kind = match query_match {
+                QueryMatch::Node(_) => "Node",
+                QueryMatch::Property(_) => "Property",
+            };
+            let text = query_match.syntax().text();
+            let first_line = text.lines().next().unwrap();
+            format!("{kind}@{} {first_line}", query_match.range())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn query_by_path() {
+    assert_snapshot!(debug_query("/keymap/*/bindings"), @r"
+    Property@[393..424] bindings = <&kp Q &hm LSHFT A>;
+    Property@[472..492] bindings = <&trans>;
+    ");
+    assert_snapshot!(debug_query("/"), @r"
+    Node@[0..550] / {
+    ");
+    assert_snapshot!(debug_query("/behaviors/*_mods"), @r"
+    Node@[28..178] hm: homerow_mods {
+    ");
+    assert_snapshot!(debug_query("/keymap/lower_layer@*"), @r"
+    Node@[444..540] lower_layer@1 {
+    ");
+    assert_snapshot!(debug_query("/keymap/lower_layer"), @r"
+    Node@[444..540] lower_layer@1 {
+    ");
+    // Only children are selected
+    assert_snapshot!(debug_query("/bindings"), @"");
+}
+
+#[test]
+fn query_at_any_depth() {
+    assert_snapshot!(debug_query("bindings"), @r"
+    Property@[393..424] bindings = <&kp Q &hm LSHFT A>;
+    Property@[472..492] bindings = <&trans>;
+    ");
+    assert_snapshot!(debug_query("/**/bindings"), @r"
+    Property@[393..424] bindings = <&kp Q &hm LSHFT A>;
+    Property@[472..492] bindings = <&trans>;
+    ");
+    assert_snapshot!(debug_query("keymap/**/*"), @r#"
+    Property@[330..356] compatible = "zmk,keymap";
+    Node@[365..435] default_layer {
+    Property@[393..424] bindings = <&kp Q &hm LSHFT A>;
+    Node@[444..540] lower_layer@1 {
+    Property@[472..492] bindings = <&trans>;
+    Property@[505..529] label = "say \"lower\"";
+    "#);
+    assert_snapshot!(debug_query("&td"), @r"
+    Node@[187..300] td: tap_dance {
+    ");
+}
+
+#[test]
+fn query_by_property() {
+    assert_snapshot!(debug_query(r#"[compatible="zmk,behavior-hold-tap"]"#), @r"
+    Node@[28..178] hm: homerow_mods {
+    ");
+    assert_snapshot!(debug_query("[hold-trigger-on-release]"), @r"
+    Node@[28..178] hm: homerow_mods {
+    ");
+    assert_snapshot!(debug_query("/behaviors/*[#binding-cells=<0>]"), @r"
+    Node@[187..300] td: tap_dance {
+    ");
+    assert_snapshot!(debug_query(r#"*[bindings][label="say \"lower\""]/bindings"#), @r"
+    Property@[472..492] bindings = <&trans>;
+    ");
+    assert_snapshot!(debug_query(r#"[compatible="zmk,keymap"]/*/bindings"#), @r"
+    Property@[393..424] bindings = <&kp Q &hm LSHFT A>;
+    Property@[472..492] bindings = <&trans>;
+    ");
+}
+
+#[test]
+fn reject_invalid_queries() {
+    let errors = [
+        "", "/keymap/", "a//b", "**", "&", "[", "[]", "[a=\"b]", "a[b]c",
+    ]
+    .map(|selector| format!("{selector:?}: {}", Query::new(selector).unwrap_err()));
+    assert_snapshot!(errors.join("\n"), @r#"
+    "": Expected a name, a label or a filter at 0
+    "/keymap/": Expected a name, a label or a filter at 8
+    "a//b": Expected a name, a label or a filter at 2
+    "**": Expected a step after `**` at 2
+    "&": Expected a label after `&` at 1
+    "[": Expected a property name at 1
+    "[]": Expected a property name at 1
+    "[a=\"b]": Unterminated string at 6
+    "a[b]c": Expected `/`, but found `c` at 4
+    "#);
+}
@@ -0,0 +1,88 @@
+use insta::assert_snapshot;
+
+use crate::{
+    ast::AstNode,
+    lexer::Token,
+    parser::parse,
+    source::Source,
+    syntax::{SyntaxNode, SyntaxNodeChild, SyntaxToken},
+};
+
+#[test]
+fn print_syntax_tree_losslessly() {
+    let test_strs = [
+        include_str!("../../tests/glove80.keymap"),
+        "",
+        "  \n// only comments\n",
+        "/ {\n\t/* This is a label */label = \"BT_2\"; // End of label\r\n\n    node {  };\n};\n",
+        "/ {\"zmk,behavior-tap-dance\"; ? arr = <1 2 &kp>;",
+    ];
+    for test_str in test_strs {
+        let source = Source::new(test_str);
+        let (doc, _) = parse(&source);
+        assert_eq!(doc.syntax().to_string(), test_str);
+    }
+}
+
+#[test]
+fn attach_trivia_to_tokens() {
+    let test_str = r#"/* file */
+/ { // open
+    // leading
+    prop = <1>; /* trailing */ // trailing
+
+    node {};
+};
+// end
+"#;
+    assert_snapshot!(debug_tokens(test_str), @r#"
+    ["/* file */", "\n"] ROOT(/) [" "]
+    [] L_CURLY({) [" ", "// open"]
+    ["\n", "    ", "// leading", "\n", "    "] NAME(prop) [" "]
+    [] EQUAL(=) [" "]
+    [] L_ANGLE(<) []
+    [] INT(1) []
+    [] R_ANGLE(>) []
+    [] SEMICOLON(;) [" ", "/* trailing */", " ", "// trailing"]
+    ["\n", "\n", "    "] NAME(node) [" "]
+    [] L_CURLY({) []
+    [] R_CURLY(}) []
+    [] SEMICOLON(;) []
+    ["\n"] R_CURLY(}) []
+    [] SEMICOLON(;) []
+    ["\n", "// end", "\n"] EOF() []
+    "#);
+}
+
+fn debug_tokens(test_str: &str) -> String {
+    let source = Source::new(test_str);
+    let (doc, _) = parse(&source);
+    let trivia_text = |trivia: Vec<Token>| {
+        trivia
+            .into_iter()
+            .map(|trivia| source.text(trivia.range))
+            .collect::<Vec<_>>()
+    };
+    let mut output = String::new();
+    for token in tokens(doc.syntax()) {
+        output.push_str(&format!(
+            "{:?} {}({}) {:?}\n",
+            trivia_text(token.leading_trivia()),
+            token.kind(),
+            source.text(token.range()),
+            trivia_text(token.trailing_trivia()),
+        ));
+    }
+    output
+}
+
+fn tokens(syntax: &SyntaxNode) -> Vec<SyntaxToken> {
+    let mut collected = Vec::new();
+    for child in syntax.children() {
+        match child {
+            SyntaxNodeChild::Token(token) => collected.push(token),
+            SyntaxNodeChild::Tree(node) => collected.append(&mut tokens(&node)),
+        }
+    }
+    collected
+}
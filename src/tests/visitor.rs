@@ -0,0 +1,197 @@
+use insta::assert_snapshot;
+
+use crate::{
+    ast::{
+        ArrayCell, AstNode, Label, NodeAddress, NodeDefinition, NonBoolPropertyDefinition,
+        PropertyDefinition, Statement,
+    },
+    parser::parse,
+    source::Source,
+    syntax::WalkEvent,
+    visitor::{walk_document, walk_node_definition, Visitor},
+};
+
+const TEST_STR: &str = r#"/ {
+    behaviors {
+        td: tap_dance@0 {
+            compatible = "zmk,behavior-tap-dance";
+            tapping-term-ms = <200>;
+            hold-trigger-on-release;
+        };
+    };
+};
+"#;
+
+#[derive(Default)]
+struct EventRecorder {
+    depth: usize,
+    events: Vec<String>,
+}
+
+impl EventRecorder {
+    fn enter(&mut self, name: &str, node: &impl AstNode) {
+        self.events.push(format!(
+            "{}enter {name} {}",
+            "  ".repeat(self.depth),
+            node.range()
+        ));
+        self.depth += 1;
+    }
+
+    fn leave(&mut self, name: &str) {
+        self.depth -= 1;
+        self.events
+            .push(format!("{}leave {name}", "  ".repeat(self.depth)));
+    }
+}
+
+impl Visitor for EventRecorder {
+    fn enter_node_definition(&mut self, node: &NodeDefinition) {
+        self.enter("node", node);
+    }
+
+    fn leave_node_definition(&mut self, _node: &NodeDefinition) {
+        self.leave("node");
+    }
+
+    fn enter_label(&mut self, label: &Label) {
+        self.enter("label", label);
+    }
+
+    fn leave_label(&mut self, _label: &Label) {
+        self.leave("label");
+    }
+
+    fn enter_node_address(&mut self, address: &NodeAddress) {
+        self.enter("address", address);
+    }
+
+    fn leave_node_address(&mut self, _address: &NodeAddress) {
+        self.leave("address");
+    }
+
+    fn enter_property_definition(&mut self, property: &PropertyDefinition) {
+        self.enter("property", property);
+    }
+
+    fn leave_property_definition(&mut self, _property: &PropertyDefinition) {
+        self.leave("property");
+    }
+
+    fn enter_array_cell(&mut self, cell: &ArrayCell) {
+        self.enter("cell", cell);
+    }
+
+    fn leave_array_cell(&mut self, _cell: &ArrayCell) {
+        self.leave("cell");
+    }
+}
+
+#[test]
+fn visit_nodes_in_order() {
+    let source = Source::new(TEST_STR);
+    let (doc, _) = parse(&source);
+    let mut recorder = EventRecorder::default();
+    walk_document(&mut recorder, &doc);
+    assert_snapshot!(recorder.events.join("\n"), @r"
+    enter node [0..191]
+      enter node [8..188]
+        enter node [28..181]
+          enter label [28..31]
+          leave label
+          enter address [41..43]
+          leave address
+          enter property [58..96]
+          leave property
+          enter property [109..133]
+            enter cell [128..131]
+            leave cell
+          leave property
+          enter property [146..170]
+          leave property
+        leave node
+      leave node
+    leave node
+    ");
+}
+
+#[test]
+fn extract_properties_with_visitor() {
+    struct CompatibleCollector<'a> {
+        source: &'a Source<'a>,
+        compatibles: Vec<String>,
+    }
+
+    impl Visitor for CompatibleCollector<'_> {
+        fn enter_non_bool_property_definition(&mut self, property: &NonBoolPropertyDefinition) {
+            let Ok(name) = property.name() else { return };
+            if self.source.text(name.range()) == "compatible" {
+                let values = property.values().unwrap();
+                for value in values.values() {
+                    self.compatibles
+                        .push(self.source.text(value.range()).to_string());
+                }
+            }
+        }
+    }
+
+    let source = Source::new(TEST_STR);
+    let (doc, _) = parse(&source);
+    let mut collector = CompatibleCollector {
+        source: &source,
+        compatibles: Vec::new(),
+    };
+    walk_document(&mut collector, &doc);
+    assert_eq!(collector.compatibles, ["\"zmk,behavior-tap-dance\""]);
+}
+
+#[test]
+fn walk_subtree() {
+    let source = Source::new(TEST_STR);
+    let (doc, _) = parse(&source);
+    let mut document_recorder = EventRecorder::default();
+    walk_document(&mut document_recorder, &doc);
+
+    let Statement::Node(root) = doc.statements().into_iter().next().unwrap();
+    let mut root_recorder = EventRecorder::default();
+    walk_node_definition(&mut root_recorder, &root);
+    assert_eq!(root_recorder.events, document_recorder.events);
+}
+
+#[test]
+fn walk_syntax_tree_in_preorder() {
+    let source = Source::new("/ { node { prop; }; };");
+    let (doc, _) = parse(&source);
+    let mut depth = 0;
+    let mut events = Vec::new();
+    for event in doc.syntax().preorder() {
+        match event {
+            WalkEvent::Enter(node) => {
+                events.push(format!(
+                    "{}{:?}@{}",
+                    "  ".repeat(depth),
+                    node.kind(),
+                    node.range()
+                ));
+                depth += 1;
+            }
+            WalkEvent::Leave(_) => depth -= 1,
+        }
+    }
+    assert_eq!(depth, 0);
+    assert_snapshot!(events.join("\n"), @r"
+    Document@[0..22]
+      NodeDefinition@[0..22]
+        RootNodeIdentifier@[0..1]
+        NodeBody@[2..22]
+          NodeBodyEntries@[4..19]
+            NodeDefinition@[4..19]
+              NonRootNodeIdentifier@[4..8]
+                NodeName@[4..8]
+              NodeBody@[9..19]
+                NodeBodyEntries@[11..16]
+                  BoolPropertyDefinition@[11..16]
+                    PropertyName@[11..15]
+    ");
+    assert_eq!(doc.syntax().descendants().count(), events.len());
+}
@@ -0,0 +1,288 @@
+//! Walk the typed AST.
+//!
+//! Implement [`Visitor`] overriding the hooks of the nodes you're interested in,
+//! then hand it to [`walk_document`], or to the `walk_*` function of any other node
+//! to only walk its subtree. Every node is entered before, and left after its children,
+//! in source order. Children that are missing because of syntax errors are skipped.
+
+use crate::ast::{
+    ArrayCell, ArrayValue, BoolPropertyDefinition, Document, IntCell, Label, MacroCell,
+    NodeAddress, NodeBody, NodeBodyEntries, NodeBodyEntry, NodeDefinition, NodeIdentifier,
+    NodeName, NonBoolPropertyDefinition, NonRootNodeIdentifier, PhandleCell, PropertyDefinition,
+    PropertyName, PropertyValue, PropertyValues, RootNodeIdentifier, Statement, StringValue,
+};
+
+#[allow(unused_variables)]
+pub trait Visitor {
+    fn enter_document(&mut self, document: &Document) {}
+    fn leave_document(&mut self, document: &Document) {}
+
+    fn enter_statement(&mut self, statement: &Statement) {}
+    fn leave_statement(&mut self, statement: &Statement) {}
+
+    fn enter_node_definition(&mut self, node: &NodeDefinition) {}
+    fn leave_node_definition(&mut self, node: &NodeDefinition) {}
+
+    fn enter_label(&mut self, label: &Label) {}
+    fn leave_label(&mut self, label: &Label) {}
+
+    fn enter_node_identifier(&mut self, identifier: &NodeIdentifier) {}
+    fn leave_node_identifier(&mut self, identifier: &NodeIdentifier) {}
+
+    fn enter_root_node_identifier(&mut self, identifier: &RootNodeIdentifier) {}
+    fn leave_root_node_identifier(&mut self, identifier: &RootNodeIdentifier) {}
+
+    fn enter_non_root_node_identifier(&mut self, identifier: &NonRootNodeIdentifier) {}
+    fn leave_non_root_node_identifier(&mut self, identifier: &NonRootNodeIdentifier) {}
+
+    fn enter_node_name(&mut self, name: &NodeName) {}
+    fn leave_node_name(&mut self, name: &NodeName) {}
+
+    fn enter_node_address(&mut self, address: &NodeAddress) {}
+    fn leave_node_address(&mut self, address: &NodeAddress) {}
+
+    fn enter_node_body(&mut self, body: &NodeBody) {}
+    fn leave_node_body(&mut self, body: &NodeBody) {}
+
+    fn enter_node_body_entries(&mut self, entries: &NodeBodyEntries) {}
+    fn leave_node_body_entries(&mut self, entries: &NodeBodyEntries) {}
+
+    fn enter_node_body_entry(&mut self, entry: &NodeBodyEntry) {}
+    fn leave_node_body_entry(&mut self, entry: &NodeBodyEntry) {}
+
+    fn enter_property_definition(&mut self, property: &PropertyDefinition) {}
+    fn leave_property_definition(&mut self, property: &PropertyDefinition) {}
+
+    fn enter_bool_property_definition(&mut self, property: &BoolPropertyDefinition) {}
+    fn leave_bool_property_definition(&mut self, property: &BoolPropertyDefinition) {}
+
+    fn enter_non_bool_property_definition(&mut self, property: &NonBoolPropertyDefinition) {}
+    fn leave_non_bool_property_definition(&mut self, property: &NonBoolPropertyDefinition) {}
+
+    fn enter_property_name(&mut self, name: &PropertyName) {}
+    fn leave_property_name(&mut self, name: &PropertyName) {}
+
+    fn enter_property_values(&mut self, values: &PropertyValues) {}
+    fn leave_property_values(&mut self, values: &PropertyValues) {}
+
+    fn enter_property_value(&mut self, value: &PropertyValue) {}
+    fn leave_property_value(&mut self, value: &PropertyValue) {}
+
+    fn enter_array_value(&mut self, array: &ArrayValue) {}
+    fn leave_array_value(&mut self, array: &ArrayValue) {}
+
+    fn enter_array_cell(&mut self, cell: &ArrayCell) {}
+    fn leave_array_cell(&mut self, cell: &ArrayCell) {}
+
+    fn enter_int_cell(&mut self, cell: &IntCell) {}
+    fn leave_int_cell(&mut self, cell: &IntCell) {}
+
+    fn enter_phandle_cell(&mut self, cell: &PhandleCell) {}
+    fn leave_phandle_cell(&mut self, cell: &PhandleCell) {}
+
+    fn enter_macro_cell(&mut self, cell: &MacroCell) {}
+    fn leave_macro_cell(&mut self, cell: &MacroCell) {}
+
+    fn enter_string_value(&mut self, string: &StringValue) {}
+    fn leave_string_value(&mut self, string: &StringValue) {}
+}
+
+pub fn walk_document<V: Visitor + ?Sized>(visitor: &mut V, document: &Document) {
+    visitor.enter_document(document);
+    for statement in document.statements() {
+        walk_statement(visitor, &statement);
+    }
+    visitor.leave_document(document);
+}
+
+pub fn walk_statement<V: Visitor + ?Sized>(visitor: &mut V, statement: &Statement) {
+    visitor.enter_statement(statement);
+    match statement {
+        Statement::Node(node) => walk_node_definition(visitor, node),
+    }
+    visitor.leave_statement(statement);
+}
+
+pub fn walk_node_definition<V: Visitor + ?Sized>(visitor: &mut V, node: &NodeDefinition) {
+    visitor.enter_node_definition(node);
+    if let Some(label) = node.label() {
+        walk_label(visitor, &label);
+    }
+    if let Ok(identifier) = node.identifier() {
+        walk_node_identifier(visitor, &identifier);
+    }
+    if let Ok(body) = node.body() {
+        walk_node_body(visitor, &body);
+    }
+    visitor.leave_node_definition(node);
+}
+
+pub fn walk_label<V: Visitor + ?Sized>(visitor: &mut V, label: &Label) {
+    visitor.enter_label(label);
+    visitor.leave_label(label);
+}
+
+pub fn walk_node_identifier<V: Visitor + ?Sized>(visitor: &mut V, identifier: &NodeIdentifier) {
+    visitor.enter_node_identifier(identifier);
+    match identifier {
+        NodeIdentifier::Root(root) => walk_root_node_identifier(visitor, root),
+        NodeIdentifier::NonRoot(identifier) => walk_non_root_node_identifier(visitor, identifier),
+    }
+    visitor.leave_node_identifier(identifier);
+}
+
+pub fn walk_root_node_identifier<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    identifier: &RootNodeIdentifier,
+) {
+    visitor.enter_root_node_identifier(identifier);
+    visitor.leave_root_node_identifier(identifier);
+}
+
+pub fn walk_non_root_node_identifier<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    identifier: &NonRootNodeIdentifier,
+) {
+    visitor.enter_non_root_node_identifier(identifier);
+    if let Ok(name) = identifier.name() {
+        walk_node_name(visitor, &name);
+    }
+    if let Some(address) = identifier.address() {
+        walk_node_address(visitor, &address);
+    }
+    visitor.leave_non_root_node_identifier(identifier);
+}
+
+pub fn walk_node_name<V: Visitor + ?Sized>(visitor: &mut V, name: &NodeName) {
+    visitor.enter_node_name(name);
+    visitor.leave_node_name(name);
+}
+
+pub fn walk_node_address<V: Visitor + ?Sized>(visitor: &mut V, address: &NodeAddress) {
+    visitor.enter_node_address(address);
+    visitor.leave_node_address(address);
+}
+
+pub fn walk_node_body<V: Visitor + ?Sized>(visitor: &mut V, body: &NodeBody) {
+    visitor.enter_node_body(body);
+    if let Ok(entries) = body.entries() {
+        walk_node_body_entries(visitor, &entries);
+    }
+    visitor.leave_node_body(body);
+}
+
+pub fn walk_node_body_entries<V: Visitor + ?Sized>(visitor: &mut V, entries: &NodeBodyEntries) {
+    visitor.enter_node_body_entries(entries);
+    for entry in entries.entries() {
+        walk_node_body_entry(visitor, &entry);
+    }
+    visitor.leave_node_body_entries(entries);
+}
+
+pub fn walk_node_body_entry<V: Visitor + ?Sized>(visitor: &mut V, entry: &NodeBodyEntry) {
+    visitor.enter_node_body_entry(entry);
+    match entry {
+        NodeBodyEntry::Node(node) => walk_node_definition(visitor, node),
+        NodeBodyEntry::Property(property) => walk_property_definition(visitor, property),
+    }
+    visitor.leave_node_body_entry(entry);
+}
+
+pub fn walk_property_definition<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    property: &PropertyDefinition,
+) {
+    visitor.enter_property_definition(property);
+    match property {
+        PropertyDefinition::Bool(property) => walk_bool_property_definition(visitor, property),
+        PropertyDefinition::NonBool(property) => {
+            walk_non_bool_property_definition(visitor, property)
+        }
+    }
+    visitor.leave_property_definition(property);
+}
+
+pub fn walk_bool_property_definition<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    property: &BoolPropertyDefinition,
+) {
+    visitor.enter_bool_property_definition(property);
+    if let Ok(name) = property.name() {
+        walk_property_name(visitor, &name);
+    }
+    visitor.leave_bool_property_definition(property);
+}
+
+pub fn walk_non_bool_property_definition<V: Visitor + ?Sized>(
+    visitor: &mut V,
+    property: &NonBoolPropertyDefinition,
+) {
+    visitor.enter_non_bool_property_definition(property);
+    if let Ok(name) = property.name() {
+        walk_property_name(visitor, &name);
+    }
+    if let Ok(values) = property.values() {
+        walk_property_values(visitor, &values);
+    }
+    visitor.leave_non_bool_property_definition(property);
+}
+
+pub fn walk_property_name<V: Visitor + ?Sized>(visitor: &mut V, name: &PropertyName) {
+    visitor.enter_property_name(name);
+    visitor.leave_property_name(name);
+}
+
+pub fn walk_property_values<V: Visitor + ?Sized>(visitor: &mut V, values: &PropertyValues) {
+    visitor.enter_property_values(values);
+    for value in values.values() {
+        walk_property_value(visitor, &value);
+    }
+    visitor.leave_property_values(values);
+}
+
+pub fn walk_property_value<V: Visitor + ?Sized>(visitor: &mut V, value: &PropertyValue) {
+    visitor.enter_property_value(value);
+    match value {
+        PropertyValue::Array(array) => walk_array_value(visitor, array),
+        PropertyValue::String(string) => walk_string_value(visitor, string),
+    }
+    visitor.leave_property_value(value);
+}
+
+pub fn walk_array_value<V: Visitor + ?Sized>(visitor: &mut V, array: &ArrayValue) {
+    visitor.enter_array_value(array);
+    for cell in array.cells() {
+        walk_array_cell(visitor, &cell);
+    }
+    visitor.leave_array_value(array);
+}
+
+pub fn walk_array_cell<V: Visitor + ?Sized>(visitor: &mut V, cell: &ArrayCell) {
+    visitor.enter_array_cell(cell);
+    match cell {
+        ArrayCell::Int(cell) => walk_int_cell(visitor, cell),
+        ArrayCell::Phandle(cell) => walk_phandle_cell(visitor, cell),
+        ArrayCell::Macro(cell) => walk_macro_cell(visitor, cell),
+    }
+    visitor.leave_array_cell(cell);
+}
+
+pub fn walk_int_cell<V: Visitor + ?Sized>(visitor: &mut V, cell: &IntCell) {
+    visitor.enter_int_cell(cell);
+    visitor.leave_int_cell(cell);
+}
+
+pub fn walk_phandle_cell<V: Visitor + ?Sized>(visitor: &mut V, cell: &PhandleCell) {
+    visitor.enter_phandle_cell(cell);
+    visitor.leave_phandle_cell(cell);
+}
+
+pub fn walk_macro_cell<V: Visitor + ?Sized>(visitor: &mut V, cell: &MacroCell) {
+    visitor.enter_macro_cell(cell);
+    visitor.leave_macro_cell(cell);
+}
+
+pub fn walk_string_value<V: Visitor + ?Sized>(visitor: &mut V, string: &StringValue) {
+    visitor.enter_string_value(string);
+    visitor.leave_string_value(string);
+}
@@ -1,6 +1,6 @@
 use std::fs;
 
-use zmk_styler::format;
+use zmk_styler::{format, format_with_config, Config};
 
 #[test]
 fn test_formatter() {
@@ -12,3 +12,31 @@ fn test_formatter() {
     let out = fs::read_to_string(out_file).unwrap();
     settings.bind(|| insta::assert_snapshot!("formatted", out))
 }
+
+#[test]
+fn test_formatter_rejects_invalid_utf8() {
+    let in_file = std::env::temp_dir().join("zmk-styler-invalid-utf8.keymap");
+    fs::write(&in_file, b"/ {\n    label = \"\xff\";\n};\n").unwrap();
+    let in_file = in_file.to_str().unwrap();
+    let error = format(in_file, in_file).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!("{in_file}:2:14: Invalid UTF-8 sequence")
+    );
+}
+
+#[test]
+fn test_formatter_verifies_output() {
+    let out_file = std::env::temp_dir().join("zmk-styler-verified.keymap");
+    let out_file = out_file.to_str().unwrap();
+    let config = Config {
+        verify: true,
+        ..Config::default()
+    };
+    format_with_config("tests/glove80.keymap", out_file, &config).unwrap();
+    let verified = fs::read_to_string(out_file).unwrap();
+    assert_eq!(
+        verified,
+        fs::read_to_string("tests/formatted.keymap").unwrap()
+    );
+}
@@ -0,0 +1,12 @@
+use zmk_styler::{query_file, Query};
+
+#[test]
+fn test_query_file() {
+    let query =
+        Query::new(r#"/behaviors/*[compatible="zmk,behavior-tap-dance"]/tapping-term-ms"#).unwrap();
+    let matches = query_file("tests/glove80.keymap", &query).unwrap();
+    assert_eq!(
+        matches,
+        ["tests/glove80.keymap:9:13: tapping-term-ms = <200>;"]
+    );
+}